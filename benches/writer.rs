@@ -0,0 +1,58 @@
+//! Compares the throughput of the default writer, which locks a single buffer for every event, with the sharded
+//! writer when several threads emit events concurrently.
+//!
+//! The collected text is cleared between iterations, which is not measured, so the sharded writer's merge is only
+//! measured when its shards are merged while events are emitted.
+//!
+//! Run with `cargo bench --bench writer`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::thread;
+use tracing_collector::{Echo, TracingCollector};
+
+const EVENTS_PER_THREAD: u64 = 10_000;
+
+fn emit(log: &TracingCollector, threads: u64) {
+    thread::scope(|s| {
+        for t in 0..threads {
+            s.spawn(move || {
+                let _guard = log.bind();
+                for i in 0..EVENTS_PER_THREAD {
+                    tracing::info!(t, i, "Handled request");
+                }
+            });
+        }
+    });
+}
+
+fn writer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("writer");
+    for threads in [1, 4, 8] {
+        group.throughput(Throughput::Elements(threads * EVENTS_PER_THREAD));
+        for sharded in [false, true] {
+            let log = TracingCollector::builder()
+                .compact()
+                .with_file(false)
+                .with_line_number(false)
+                .with_echo(Echo::Off)
+                .with_sharded_writer(sharded)
+                .init();
+            let name = if sharded { "sharded" } else { "mutex" };
+            group.bench_with_input(BenchmarkId::new(name, threads), &threads, |b, &threads| {
+                b.iter_batched(
+                    || log.clear(),
+                    |()| emit(&log, threads),
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = writer
+}
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+use std::fmt::Write;
+
+use crate::event::CollectedEvent;
+use crate::matcher::EventMatcher;
+use crate::TracingCollector;
+
+/// Assertions on the structured events, which don't consume them. On failure, they panic with a description of the
+/// collected events and, where relevant, how the closest event differs from what was expected.
+impl<M> TracingCollector<M> {
+    /// Assert that at least one collected event matches.
+    ///
+    /// Accepts an [`EventMatcher`] or a `Level`, e.g. `log.assert_contains(Level::WARN)`.
+    #[track_caller]
+    pub fn assert_contains(&self, matcher: impl Into<EventMatcher>) {
+        let matcher = matcher.into();
+        let events = self.events();
+        if !events.iter().any(|event| matcher.matches(event)) {
+            panic!(
+                "expected an event matching `{matcher}`\n{}",
+                describe(&matcher, &events, "collected events")
+            );
+        }
+    }
+
+    /// Assert that no collected event matches, e.g. `log.assert_not_logged(Level::ERROR)`.
+    #[track_caller]
+    pub fn assert_not_logged(&self, matcher: impl Into<EventMatcher>) {
+        let matcher = matcher.into();
+        let events = self.events();
+        if let Some(event) = events.iter().find(|event| matcher.matches(event)) {
+            panic!("expected no event matching `{matcher}`, found:\n  {event}\n");
+        }
+    }
+
+    /// Assert that exactly `count` collected events match.
+    #[track_caller]
+    pub fn assert_count(&self, matcher: impl Into<EventMatcher>, count: usize) {
+        let matcher = matcher.into();
+        let events = self.events();
+        let matching: Vec<_> = events
+            .iter()
+            .filter(|event| matcher.matches(event))
+            .collect();
+        if matching.len() != count {
+            let mut message = format!(
+                "expected {count} events matching `{matcher}`, found {}:\n",
+                matching.len()
+            );
+            for event in matching {
+                writeln!(message, "  {event}").unwrap();
+            }
+            panic!("{message}");
+        }
+    }
+
+    /// Assert that events matching each matcher were collected in that order. Other events may be collected
+    /// before, between or after them.
+    #[track_caller]
+    pub fn assert_sequence<T: Into<EventMatcher>>(&self, matchers: impl IntoIterator<Item = T>) {
+        let events = self.events();
+        let mut remaining = &events[..];
+        for (index, matcher) in matchers.into_iter().enumerate() {
+            let matcher = matcher.into();
+            match remaining.iter().position(|event| matcher.matches(event)) {
+                Some(position) => remaining = &remaining[position + 1..],
+                None => panic!(
+                    "expected event #{index} of the sequence to match `{matcher}` after the previous ones\n{}",
+                    describe(&matcher, remaining, "events after the previous ones")
+                ),
+            }
+        }
+    }
+}
+
+/// Describe the closest event to the matcher, i.e. the one satisfying the most conditions, and list the events
+/// under the heading.
+fn describe(matcher: &EventMatcher, events: &[CollectedEvent], heading: &str) -> String {
+    let mut description = String::new();
+    // `max_by_key` returns the last maximum, so reverse to report the first one.
+    let closest = events.iter().rev().max_by_key(|event| matcher.score(event));
+    if let Some(closest) = closest {
+        writeln!(description, "closest event:\n  {closest}").unwrap();
+        description.push_str(&matcher.diff(closest));
+    }
+    writeln!(description, "{heading} ({}):", events.len()).unwrap();
+    for event in events {
+        writeln!(description, "  {event}").unwrap();
+    }
+    description
+}
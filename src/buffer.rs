@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+
+/// A position in the traces collected by a [`TracingCollector`](crate::TracingCollector).
+/// Created with [`TracingCollector::checkpoint`](crate::TracingCollector::checkpoint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub(crate) text: usize,
+    pub(crate) events: usize,
+}
+
+/// The maximum size of a [`Buffer`], in items (e.g. bytes of text) and in event records (the groups of items added
+/// together for an event, e.g. its text). The oldest records are evicted to stay within both.
+///
+/// Records that are not events, e.g. the text of span lifecycle events, don't count toward `max_records` until there
+/// are more than `max_records` of them, so that they stay bounded without events. Until then, they are evicted with
+/// the event that follows them, or to stay within `max_items`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Limit {
+    pub(crate) max_items: Option<usize>,
+    pub(crate) max_records: Option<usize>,
+}
+
+impl Limit {
+    fn is_bounded(&self) -> bool {
+        self.max_items.is_some() || self.max_records.is_some()
+    }
+
+    /// Whether `items` in `records`, of which `events` are events, exceed the limit.
+    pub(crate) fn is_exceeded(&self, items: usize, records: usize, events: usize) -> bool {
+        let over_items = self.max_items.is_some_and(|max_items| items > max_items);
+        let over_records = self.max_records.is_some_and(|max_records| {
+            events + (records - events).saturating_sub(max_records) > max_records
+        });
+        over_items || over_records
+    }
+}
+
+/// Collected items (bytes of text or events) along with the number of items that were already consumed,
+/// so that positions in the buffer stay valid when it is consumed.
+///
+/// When the buffer is bounded, the oldest records are evicted like in a ring buffer. Evicted items count as
+/// consumed, and are only removed from the `Vec` once they make up half of it to keep eviction cheap.
+#[derive(Debug)]
+pub(crate) struct Buffer<T> {
+    items: Vec<T>,
+    /// The number of evicted items at the start of `items`.
+    evicted: usize,
+    /// The position of the first item that is neither consumed nor evicted.
+    consumed: usize,
+    limit: Limit,
+    /// The number of items of each record in the buffer and whether it is an event, oldest first, when it is
+    /// bounded.
+    records: VecDeque<(usize, bool)>,
+    /// The number of event records in `records`.
+    events: usize,
+    /// The number of items evicted since the buffer was created.
+    dropped: usize,
+    /// The number of event records evicted since the buffer was created.
+    dropped_events: usize,
+}
+
+impl<T> Default for Buffer<T> {
+    fn default() -> Self {
+        Self {
+            items: vec![],
+            evicted: 0,
+            consumed: 0,
+            limit: Limit::default(),
+            records: VecDeque::new(),
+            events: 0,
+            dropped: 0,
+            dropped_events: 0,
+        }
+    }
+}
+
+impl<T> Buffer<T> {
+    pub(crate) fn bounded(limit: Limit) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn items(&self) -> &[T] {
+        &self.items[self.evicted..]
+    }
+
+    pub(crate) fn push(&mut self, item: T) {
+        self.items.push(item);
+        self.record(1, true);
+    }
+
+    /// The position after the last collected item.
+    pub(crate) fn end(&self) -> usize {
+        self.consumed + self.items().len()
+    }
+
+    /// The items collected since the position, or all items if the position was already consumed.
+    pub(crate) fn since(&self, position: usize) -> &[T] {
+        let items = self.items();
+        let start = position.saturating_sub(self.consumed).min(items.len());
+        &items[start..]
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<T> {
+        let items = self.items.split_off(self.evicted);
+        self.consumed += items.len();
+        self.clear();
+        items
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.consumed += self.items().len();
+        self.items.clear();
+        self.evicted = 0;
+        self.records.clear();
+        self.events = 0;
+    }
+
+    /// The number of items that were evicted to stay within the limit.
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// The number of event records that were evicted to stay within the limit.
+    pub(crate) fn dropped_events(&self) -> usize {
+        self.dropped_events
+    }
+
+    /// Track the record of the last `len` items and evict the oldest records if the buffer is over its limit.
+    fn record(&mut self, len: usize, is_event: bool) {
+        if !self.limit.is_bounded() {
+            return;
+        }
+        self.records.push_back((len, is_event));
+        self.events += usize::from(is_event);
+        while self
+            .limit
+            .is_exceeded(self.items().len(), self.records.len(), self.events)
+        {
+            let Some((len, is_event)) = self.records.pop_front() else {
+                break;
+            };
+            self.events -= usize::from(is_event);
+            self.dropped_events += usize::from(is_event);
+            self.evicted += len;
+            self.consumed += len;
+            self.dropped += len;
+        }
+        if self.evicted > self.items.len() / 2 {
+            self.items.drain(..self.evicted);
+            self.evicted = 0;
+        }
+    }
+}
+
+impl<T: Clone> Buffer<T> {
+    /// Add the items as one record, which counts toward the limit's `max_records` if it is an event.
+    pub(crate) fn extend_from_slice(&mut self, items: &[T], is_event: bool) {
+        self.items.extend_from_slice(items);
+        self.record(items.len(), is_event);
+    }
+}
@@ -0,0 +1,439 @@
+use std::{
+    env, fmt,
+    sync::{Arc, Mutex},
+};
+use tracing::{Dispatch, Level, Metadata, Subscriber};
+use tracing_subscriber::{
+    filter::{filter_fn, EnvFilter, LevelFilter},
+    fmt::format::FmtSpan,
+    layer::{Filter, SubscriberExt},
+    registry::LookupSpan,
+    Layer,
+};
+
+use crate::{
+    buffer::{Buffer, Limit},
+    echo::{Echo, EchoTarget},
+    global::{self, Route},
+    layer::CaptureLayer,
+    location::{LocationRewriter, LocationSyntax, Locations},
+    redact::Redaction,
+    shard::Shards,
+    snapshot::SnapshotFormat,
+    CollectingWriter, Install, Layered, TracingCollector,
+};
+
+/// The format used for the collected text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Pretty,
+    Compact,
+    Full,
+    Json,
+    Snapshot,
+}
+
+/// Decides which spans and events are collected.
+enum CollectorFilter {
+    Level(LevelFilter),
+    Env(Box<EnvFilter>),
+    Fn(Arc<dyn Fn(&Metadata<'_>) -> bool + Send + Sync>),
+}
+
+impl CollectorFilter {
+    fn into_filter<S>(self) -> Box<dyn Filter<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        match self {
+            CollectorFilter::Level(level) => Box::new(level),
+            CollectorFilter::Env(env_filter) => env_filter,
+            CollectorFilter::Fn(f) => Box::new(filter_fn(move |metadata| f(metadata))),
+        }
+    }
+}
+
+impl fmt::Debug for CollectorFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectorFilter::Level(level) => f.debug_tuple("Level").field(level).finish(),
+            CollectorFilter::Env(env_filter) => f.debug_tuple("Env").field(env_filter).finish(),
+            CollectorFilter::Fn(_) => f.debug_tuple("Fn").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Configures the formatter of a [`TracingCollector`]. Created with [`TracingCollector::builder`].
+///
+/// The defaults are the same as for [`TracingCollector::init`]: the pretty format without timestamps and
+/// targets, with file names and line numbers, collecting traces up to the `TRACE` level.
+///
+/// Which traces are collected is decided by a single max level (`with_max_level`), `EnvFilter` directives such as
+/// `"my_crate=trace,hyper=warn"` (`with_env_filter`) or an arbitrary predicate on the metadata (`with_filter_fn`).
+/// The last one configured is used. With `with_rust_log(true)`, the `RUST_LOG` environment variable overrides it
+/// when set.
+///
+/// Example:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_collector::TracingCollector;
+///
+/// let log = TracingCollector::builder()
+///     .compact()
+///     .with_target(true)
+///     .with_file(false)
+///     .with_line_number(false)
+///     .with_max_level(Level::INFO)
+///     .init();
+/// ```
+#[derive(Debug)]
+pub struct TracingCollectorBuilder {
+    format: Format,
+    target: bool,
+    file: bool,
+    line_number: bool,
+    thread_names: bool,
+    thread_ids: bool,
+    span_events: FmtSpan,
+    filter: CollectorFilter,
+    rust_log: bool,
+    global: bool,
+    echo: Echo,
+    redactions: Vec<Redaction>,
+    locations: Locations,
+    normalize_paths: bool,
+    max_bytes: Option<usize>,
+    max_events: Option<usize>,
+    sharded_writer: bool,
+}
+
+impl Default for TracingCollectorBuilder {
+    fn default() -> Self {
+        Self {
+            format: Format::Pretty,
+            target: false,
+            file: true,
+            line_number: true,
+            thread_names: false,
+            thread_ids: false,
+            span_events: FmtSpan::NONE,
+            filter: CollectorFilter::Level(LevelFilter::TRACE),
+            rust_log: false,
+            global: false,
+            echo: Echo::Stdout,
+            redactions: vec![],
+            locations: Locations::Full,
+            normalize_paths: false,
+            max_bytes: None,
+            max_events: None,
+            sharded_writer: false,
+        }
+    }
+}
+
+impl TracingCollectorBuilder {
+    /// Use the multi-line pretty format (the default).
+    pub fn pretty(mut self) -> Self {
+        self.format = Format::Pretty;
+        self
+    }
+
+    /// Use the compact single-line format.
+    pub fn compact(mut self) -> Self {
+        self.format = Format::Compact;
+        self
+    }
+
+    /// Use the full single-line format.
+    pub fn full(mut self) -> Self {
+        self.format = Format::Full;
+        self
+    }
+
+    /// Use the JSON format, one object per line.
+    pub fn json(mut self) -> Self {
+        self.format = Format::Json;
+        self
+    }
+
+    /// Use the one-line format designed for snapshots, e.g. `INFO my_crate: request{id=1}: Handled answer=42`,
+    /// with the span path before the message and the fields sorted by name.
+    ///
+    /// This format always shows the targets and never the source locations or thread names and ids.
+    pub fn snapshot(mut self) -> Self {
+        self.format = Format::Snapshot;
+        self
+    }
+
+    /// Show the target of each event.
+    pub fn with_target(mut self, target: bool) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Show the source file of each event.
+    pub fn with_file(mut self, file: bool) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Show the line number of each event.
+    pub fn with_line_number(mut self, line_number: bool) -> Self {
+        self.line_number = line_number;
+        self
+    }
+
+    /// Show the name of the thread each event was emitted on.
+    pub fn with_thread_names(mut self, thread_names: bool) -> Self {
+        self.thread_names = thread_names;
+        self
+    }
+
+    /// Show the id of the thread each event was emitted on.
+    pub fn with_thread_ids(mut self, thread_ids: bool) -> Self {
+        self.thread_ids = thread_ids;
+        self
+    }
+
+    /// Format span lifecycle events (e.g. `FmtSpan::NEW | FmtSpan::CLOSE`) in addition to regular events.
+    pub fn with_span_events(mut self, span_events: FmtSpan) -> Self {
+        self.span_events = span_events;
+        self
+    }
+
+    /// Collect traces up to the specified level.
+    pub fn with_max_level(mut self, max_level: Level) -> Self {
+        self.filter = CollectorFilter::Level(LevelFilter::from_level(max_level));
+        self
+    }
+
+    /// Collect traces according to `EnvFilter` directives, e.g. `"my_crate=trace,hyper=warn"`.
+    pub fn with_env_filter(mut self, env_filter: impl Into<EnvFilter>) -> Self {
+        self.filter = CollectorFilter::Env(Box::new(env_filter.into()));
+        self
+    }
+
+    /// Collect the traces for which `filter` returns `true`.
+    pub fn with_filter_fn<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Metadata<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.filter = CollectorFilter::Fn(Arc::new(filter));
+        self
+    }
+
+    /// When `RUST_LOG` is set, use its directives instead of the configured filter.
+    pub fn with_rust_log(mut self, rust_log: bool) -> Self {
+        self.rust_log = rust_log;
+        self
+    }
+
+    /// Echo the traces while they are collected, see [`Echo`]. Defaults to `Echo::Stdout`.
+    pub fn with_echo(mut self, echo: Echo) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    /// Show the source locations as the mode when reading the collected traces, e.g. `Locations::Ordinal` to keep
+    /// snapshots stable when lines are added above a log statement. Defaults to `Locations::Full`.
+    pub fn with_locations(mut self, locations: Locations) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Make the paths of the source locations relative to the package's directory when reading the collected
+    /// traces, so that they are the same whether the paths are absolute (e.g. under `cargo llvm-cov`) or relative
+    /// to the workspace.
+    pub fn with_normalized_paths(mut self, normalize_paths: bool) -> Self {
+        self.normalize_paths = normalize_paths;
+        self
+    }
+
+    /// Apply the redaction to the collected traces when they are read, see [`Redaction`]. Can be called
+    /// repeatedly, the redactions are applied in order.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redactions.push(redaction);
+        self
+    }
+
+    /// Keep at most `max_bytes` of collected text, evicting the text of the oldest events when it is exceeded, like
+    /// a ring buffer. This bounds the text (and the copy kept for `Echo::OnFailure`), but not the structured events,
+    /// see `with_max_events`. The number of events evicted from the text is returned by
+    /// `TracingCollector::dropped_events`.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Keep at most `max_events` events, evicting the oldest ones when it is exceeded, like a ring buffer. This
+    /// bounds the text, the structured events and the span lifecycle events, each to `max_events` entries.
+    ///
+    /// The lines of the span lifecycle events (see `with_span_events`) don't count toward the limit of the text, they
+    /// are evicted with the event that follows them. Beyond `max_events` of them, e.g. when only spans are opened
+    /// and closed, they count toward the limit as well. The number of evicted events is returned by
+    /// `TracingCollector::dropped_events`.
+    pub fn with_max_events(mut self, max_events: usize) -> Self {
+        self.max_events = Some(max_events);
+        self
+    }
+
+    /// Buffer the text written by each thread separately and merge it when it is read, ordered by a sequence
+    /// number shared by all threads, instead of locking a single buffer for every event. This reduces the
+    /// contention in tests where many threads emit a lot of events. `cargo bench --bench writer` compares the
+    /// throughput of both writers.
+    pub fn with_sharded_writer(mut self, sharded_writer: bool) -> Self {
+        self.sharded_writer = sharded_writer;
+        self
+    }
+
+    /// Collect traces through the process-wide subscriber instead of the current thread's default subscriber.
+    ///
+    /// The process-wide subscriber is installed when the first global collector is created and routes the
+    /// traces emitted in spans created on a thread bound to the collector (the thread that created it, or one
+    /// bound with `TracingCollector::bind`), or emitted on such a thread, to the collector. This captures the
+    /// traces of spawned threads and tasks that run in such spans, e.g. using `Instrument::in_current_span`.
+    ///
+    /// Panics on `init` if another global subscriber has already been set.
+    pub fn global(mut self) -> Self {
+        self.global = true;
+        self
+    }
+
+    /// Create the `TracingCollector` and set its subscriber as the default for the current thread, or
+    /// register it with the process-wide subscriber when `global` is set.
+    pub fn init(self) -> TracingCollector {
+        if self.global {
+            let (mut collector, fmt, capture) = self.layers();
+            let id = global::register(Route::new(fmt, capture, self.filter().into_filter()));
+            global::bind(id);
+            collector.install = Some(Install::Global(id));
+            collector
+        } else {
+            let (layer, mut collector) = self.into_layer();
+            let dispatch = Dispatch::new(tracing_subscriber::registry().with(layer));
+            collector.set_guard(tracing::dispatcher::set_default(&dispatch));
+            collector.install = Some(Install::Thread(dispatch));
+            collector
+        }
+    }
+
+    /// Create a `Layer` collecting the traces, to add to an existing subscriber, and the `TracingCollector` to read
+    /// them from. Nothing is installed: the traces are collected wherever the subscriber is used, so the collector
+    /// has no `bind` or `instrument`.
+    ///
+    /// Panics if `global` is set, as the layer isn't registered with the process-wide subscriber.
+    pub fn build_layer<S>(self) -> (Box<dyn Layer<S> + Send + Sync>, TracingCollector<Layered>)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        assert!(
+            !self.global,
+            "`global` can't be used with `build_layer`, set the subscriber the layer is added to globally instead"
+        );
+        self.into_layer()
+    }
+
+    /// Create the `Layer` collecting the traces, with its filter, and the `TracingCollector` to read them from.
+    fn into_layer<S, M>(self) -> (Box<dyn Layer<S> + Send + Sync>, TracingCollector<M>)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let (collector, fmt, capture) = self.layers();
+        let layer = fmt
+            .and_then(capture)
+            .with_filter(self.filter().into_filter())
+            .boxed();
+        (layer, collector)
+    }
+
+    /// Create the `TracingCollector` and the layers collecting the formatted text and the events into it.
+    fn layers<S, M>(
+        &self,
+    ) -> (
+        TracingCollector<M>,
+        Box<dyn Layer<S> + Send + Sync>,
+        CaptureLayer,
+    )
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut collector = TracingCollector::new();
+
+        let text_limit = Limit {
+            max_items: self.max_bytes,
+            max_records: self.max_events,
+        };
+        let events_limit = Limit {
+            max_items: None,
+            max_records: self.max_events,
+        };
+        collector.buf = Arc::new(Mutex::new(Buffer::bounded(text_limit)));
+        collector.events = Arc::new(Mutex::new(Buffer::bounded(events_limit)));
+        collector.spans = Arc::new(Mutex::new(Buffer::bounded(events_limit)));
+        collector.echo = EchoTarget::new(&self.echo, text_limit);
+        collector.redactions = self.redactions.clone();
+        collector.locations =
+            LocationRewriter::new(self.locations, self.normalize_paths, self.location_syntax());
+        collector.shards = self
+            .sharded_writer
+            .then(|| Arc::new(Shards::new(text_limit)));
+        let writer = CollectingWriter::new(
+            collector.buf.clone(),
+            collector.shards.clone(),
+            collector.echo.clone(),
+        );
+        let fmt = self.fmt_layer(writer);
+        let capture = CaptureLayer::new(
+            collector.events.clone(),
+            collector.spans.clone(),
+            collector.notify.clone(),
+        );
+        (collector, fmt, capture)
+    }
+
+    fn filter(self) -> CollectorFilter {
+        match env::var(EnvFilter::DEFAULT_ENV) {
+            Ok(directives) if self.rust_log && !directives.is_empty() => {
+                CollectorFilter::Env(Box::new(EnvFilter::new(directives)))
+            }
+            _ => self.filter,
+        }
+    }
+
+    /// How the formatter writes the source locations.
+    fn location_syntax(&self) -> LocationSyntax {
+        if !self.file {
+            return LocationSyntax::None;
+        }
+        match self.format {
+            Format::Pretty => LocationSyntax::Pretty,
+            Format::Compact | Format::Full => LocationSyntax::Line,
+            Format::Json => LocationSyntax::Json,
+            Format::Snapshot => LocationSyntax::None,
+        }
+    }
+
+    fn fmt_layer<S>(&self, writer: CollectingWriter) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let layer = tracing_subscriber::fmt::layer()
+            .without_time()
+            .with_target(self.target)
+            .with_file(self.file)
+            .with_line_number(self.line_number)
+            .with_thread_names(self.thread_names)
+            .with_thread_ids(self.thread_ids)
+            .with_span_events(self.span_events.clone())
+            .with_ansi(true)
+            .with_writer(writer);
+        match self.format {
+            Format::Pretty => layer.pretty().boxed(),
+            Format::Compact => layer.compact().boxed(),
+            Format::Full => layer.boxed(),
+            Format::Json => layer.json().boxed(),
+            Format::Snapshot => layer.event_format(SnapshotFormat).boxed(),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use crate::buffer::{Buffer, Limit};
+use crate::lock::LockExt;
+
+/// Where the traces are echoed to while they are collected. Configured with
+/// [`TracingCollectorBuilder::with_echo`](crate::TracingCollectorBuilder::with_echo).
+///
+/// The echoed traces keep their colors (ANSI escape codes).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Echo {
+    /// Don't echo the traces.
+    Off,
+    /// Echo the traces to stdout (the default). Like `println!`, this is captured by the test harness and only
+    /// shown when the test fails or is run with `--nocapture`.
+    #[default]
+    Stdout,
+    /// Echo the traces to stderr.
+    Stderr,
+    /// Don't echo the traces while the test runs, but write all collected traces (including those that were
+    /// already read or cleared) to stderr when the `TracingCollector` is dropped while the thread is panicking,
+    /// e.g. because an assertion failed. In a bounded collector, only the most recent traces are kept for this.
+    OnFailure,
+    /// Append the traces to a file.
+    File(PathBuf),
+}
+
+/// The resolved destination of an [`Echo`].
+#[derive(Clone)]
+pub(crate) enum EchoTarget {
+    Off,
+    Stdout,
+    Stderr,
+    File(Arc<Mutex<File>>),
+    /// Keep a copy of all traces (within the limit), to be written to stderr if the test fails.
+    Replay(Arc<Mutex<Buffer<u8>>>),
+}
+
+impl EchoTarget {
+    pub(crate) fn new(echo: &Echo, limit: Limit) -> Self {
+        match echo {
+            Echo::Off => EchoTarget::Off,
+            Echo::OnFailure => EchoTarget::Replay(Arc::new(Mutex::new(Buffer::bounded(limit)))),
+            Echo::Stdout => EchoTarget::Stdout,
+            Echo::Stderr => EchoTarget::Stderr,
+            Echo::File(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .unwrap_or_else(|e| panic!("failed to open echo file {path:?}: {e}"));
+                EchoTarget::File(Arc::new(Mutex::new(file)))
+            }
+        }
+    }
+
+    /// Write the copy kept by a `Replay` target to stderr.
+    pub(crate) fn replay(&self) {
+        if let EchoTarget::Replay(history) = self {
+            let history = history.lock_or_recover();
+            eprintln!("---- collected traces ----");
+            EchoTarget::Stderr.write(history.items(), true);
+        }
+    }
+
+    /// Echo the text of an event, or of a span lifecycle event if `is_event` is false.
+    pub(crate) fn write(&self, buf: &[u8], is_event: bool) {
+        match self {
+            EchoTarget::Off => {}
+            // use the print macros rather than io::stdout() so that the test harness captures the output
+            EchoTarget::Stdout => print!("{}", String::from_utf8_lossy(buf)),
+            EchoTarget::Stderr => eprint!("{}", String::from_utf8_lossy(buf)),
+            EchoTarget::File(file) => {
+                // echoing is best effort, failing to write must not fail the collection
+                let _ = file.lock_or_recover().write_all(buf);
+            }
+            EchoTarget::Replay(history) => {
+                history.lock_or_recover().extend_from_slice(buf, is_event)
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::{error::Error, fmt, string::FromUtf8Error};
+
+/// An error reading the traces collected by a [`TracingCollector`](crate::TracingCollector), returned by
+/// [`TracingCollector::try_read`](crate::TracingCollector::try_read).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollectorError {
+    /// A thread panicked while holding the lock of the collected traces, so they may be incomplete.
+    Poisoned,
+    /// The collected traces (stripped of ANSI escape codes) are not valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+}
+
+impl fmt::Display for CollectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectorError::Poisoned => {
+                f.write_str("a thread panicked while collecting the traces")
+            }
+            CollectorError::InvalidUtf8(_) => {
+                f.write_str("the collected traces are not valid UTF-8")
+            }
+        }
+    }
+}
+
+impl Error for CollectorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CollectorError::Poisoned => None,
+            CollectorError::InvalidUtf8(e) => Some(e),
+        }
+    }
+}
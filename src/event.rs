@@ -0,0 +1,295 @@
+use serde_json::{json, Map, Value};
+use std::fmt;
+use tracing::{
+    field::{Field, Visit},
+    Level,
+};
+
+/// A single event captured by a [`TracingCollector`](crate::TracingCollector), with its fields kept as typed values.
+///
+/// Events are retrieved with [`TracingCollector::events`](crate::TracingCollector::events) and allow asserting on
+/// fields programmatically instead of matching against the formatted text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectedEvent {
+    pub level: Level,
+    pub target: &'static str,
+    /// The `message` field of the event, if any.
+    pub message: Option<String>,
+    /// All fields except `message`, in the order they were recorded.
+    pub fields: Vec<(String, FieldValue)>,
+    pub file: Option<&'static str>,
+    pub line: Option<u32>,
+    /// The spans the event was emitted in, ordered from the root to the innermost span.
+    pub spans: Vec<SpanContext>,
+}
+
+impl CollectedEvent {
+    /// Get the value of the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        find_field(&self.fields, name)
+    }
+
+    /// Convert the event to JSON using a stable schema:
+    ///
+    /// ```json
+    /// {
+    ///   "level": "INFO",
+    ///   "target": "my_crate::module",
+    ///   "message": "Hello",
+    ///   "fields": { "answer": 42 },
+    ///   "file": "src/module.rs",
+    ///   "line": 12,
+    ///   "spans": [{ "name": "request", "target": "my_crate", "fields": { "id": 1 } }]
+    /// }
+    /// ```
+    ///
+    /// Missing values (e.g. an event without a message) are `null`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "level": self.level.as_str(),
+            "target": self.target,
+            "message": self.message,
+            "fields": fields_to_json(&self.fields),
+            "file": self.file,
+            "line": self.line,
+            "spans": self.spans.iter().map(SpanContext::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Writes the event on a single line, e.g. `WARN my_crate::upload: retrying attempt=3`.
+impl fmt::Display for CollectedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}:", self.level, self.target)?;
+        if let Some(message) = &self.message {
+            write!(f, " {message}")?;
+        }
+        for (name, value) in &self.fields {
+            write!(f, " {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A span that was active when a [`CollectedEvent`] was emitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanContext {
+    pub name: &'static str,
+    pub target: &'static str,
+    /// The span's fields, including values added later with `Span::record`.
+    pub fields: Vec<(String, FieldValue)>,
+}
+
+impl SpanContext {
+    /// Get the value of the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        find_field(&self.fields, name)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "target": self.target,
+            "fields": fields_to_json(&self.fields),
+        })
+    }
+}
+
+/// The value of a field, as it was recorded by `tracing`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Str(String),
+    /// A value recorded with its `Debug` (`?value`) or `Display` (`%value`) implementation.
+    Debug(String),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Bool(v) => write!(f, "{v}"),
+            FieldValue::I64(v) => write!(f, "{v}"),
+            FieldValue::U64(v) => write!(f, "{v}"),
+            FieldValue::I128(v) => write!(f, "{v}"),
+            FieldValue::U128(v) => write!(f, "{v}"),
+            FieldValue::F64(v) => write!(f, "{v}"),
+            FieldValue::Str(v) => write!(f, "{v:?}"),
+            FieldValue::Debug(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl FieldValue {
+    /// Convert the value to JSON. Integers that don't fit in a JSON number are converted to strings.
+    pub fn to_json(&self) -> Value {
+        match self {
+            FieldValue::Bool(v) => Value::from(*v),
+            FieldValue::I64(v) => Value::from(*v),
+            FieldValue::U64(v) => Value::from(*v),
+            FieldValue::I128(v) => {
+                i64::try_from(*v).map_or_else(|_| Value::from(v.to_string()), Value::from)
+            }
+            FieldValue::U128(v) => {
+                u64::try_from(*v).map_or_else(|_| Value::from(v.to_string()), Value::from)
+            }
+            FieldValue::F64(v) => Value::from(*v),
+            FieldValue::Str(v) | FieldValue::Debug(v) => Value::from(v.as_str()),
+        }
+    }
+
+    /// Compare the value with another one, regardless of how they were recorded: integers are compared by value
+    /// regardless of their type, and strings are compared with values recorded with `Debug` or `Display`.
+    pub fn matches(&self, other: &FieldValue) -> bool {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(a), Some(b)) => return a == b,
+            (Some(_), None) | (None, Some(_)) => return false,
+            (None, None) => {}
+        }
+        match (self, other) {
+            (
+                FieldValue::Str(a) | FieldValue::Debug(a),
+                FieldValue::Str(b) | FieldValue::Debug(b),
+            ) => a == b,
+            _ => self == other,
+        }
+    }
+
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            FieldValue::I64(v) => Some(i128::from(*v)),
+            FieldValue::U64(v) => Some(i128::from(*v)),
+            FieldValue::I128(v) => Some(*v),
+            FieldValue::U128(v) => i128::try_from(*v).ok(),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for FieldValue {
+                fn from(value: $ty) -> Self {
+                    FieldValue::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    bool => Bool,
+    i8 => I64,
+    i16 => I64,
+    i32 => I64,
+    i64 => I64,
+    u8 => U64,
+    u16 => U64,
+    u32 => U64,
+    u64 => U64,
+    i128 => I128,
+    u128 => U128,
+    f32 => F64,
+    f64 => F64,
+    &str => Str,
+    String => Str,
+);
+
+fn fields_to_json(fields: &[(String, FieldValue)]) -> Value {
+    let map: Map<String, Value> = fields
+        .iter()
+        .map(|(name, value)| (name.clone(), value.to_json()))
+        .collect();
+    Value::Object(map)
+}
+
+fn find_field<'a>(fields: &'a [(String, FieldValue)], name: &str) -> Option<&'a FieldValue> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value)
+}
+
+/// Records field values into a list, replacing values of fields that are already present.
+pub(crate) struct FieldVisitor<'a> {
+    pub(crate) fields: &'a mut Vec<(String, FieldValue)>,
+    pub(crate) message: Option<&'a mut Option<String>>,
+}
+
+impl<'a> FieldVisitor<'a> {
+    pub(crate) fn new(fields: &'a mut Vec<(String, FieldValue)>) -> Self {
+        Self {
+            fields,
+            message: None,
+        }
+    }
+
+    pub(crate) fn with_message(
+        fields: &'a mut Vec<(String, FieldValue)>,
+        message: &'a mut Option<String>,
+    ) -> Self {
+        Self {
+            fields,
+            message: Some(message),
+        }
+    }
+
+    fn record(&mut self, field: &Field, value: FieldValue) {
+        if field.name() == "message" {
+            if let Some(message) = self.message.as_mut() {
+                **message = Some(match value {
+                    FieldValue::Str(s) | FieldValue::Debug(s) => s,
+                    other => other.to_string(),
+                });
+                return;
+            }
+        }
+        match self
+            .fields
+            .iter_mut()
+            .find(|(name, _)| name == field.name())
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.fields.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl<'a> Visit for FieldVisitor<'a> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, FieldValue::F64(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record(field, FieldValue::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.record(field, FieldValue::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.record(field, FieldValue::U128(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, FieldValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, FieldValue::Str(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, FieldValue::Debug(format!("{value:?}")));
+    }
+}
@@ -0,0 +1,285 @@
+//! The process-wide subscriber used by collectors created with [`TracingCollectorBuilder::global`].
+//!
+//! A single subscriber is installed as the global default the first time a global collector is created. Each
+//! global collector registers a [`Route`] with it. Events and spans are routed to a collector when they are
+//! emitted in a span that belongs to the collector, or on a thread that is bound to the collector. A span belongs
+//! to the collector its parent belongs to, or else to the collector the current thread is bound to. Events in a
+//! span that belongs to no collector, e.g. because it was created before the collector, are routed by thread.
+//!
+//! [`TracingCollectorBuilder::global`]: crate::TracingCollectorBuilder::global
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock, PoisonError, RwLock, RwLockReadGuard,
+    },
+};
+use tracing::{span, Event, Metadata, Subscriber};
+use tracing_subscriber::{
+    fmt::{
+        format::{DefaultFields, JsonFields, Pretty},
+        FormatFields, FormattedFields,
+    },
+    layer::{Context, Filter, SubscriberExt},
+    registry::{ExtensionsMut, LookupSpan, SpanRef},
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+use crate::layer::CaptureLayer;
+
+static NEXT_COLLECTOR_ID: AtomicU64 = AtomicU64::new(1);
+/// The result of installing the global subscriber, kept so that every global collector fails the same way.
+static INSTALL: OnceLock<Result<(), String>> = OnceLock::new();
+static ROUTES: RwLock<Option<HashMap<u64, Route>>> = RwLock::new(None);
+
+thread_local! {
+    /// The collectors the current thread is bound to, the last one being the current.
+    static BOUND: RefCell<Vec<u64>> = const { RefCell::new(vec![]) };
+}
+
+/// The layers and filter of a global collector.
+pub(crate) struct Route {
+    fmt: Box<dyn Layer<Registry> + Send + Sync>,
+    capture: CaptureLayer,
+    filter: Box<dyn Filter<Registry> + Send + Sync>,
+}
+
+impl Route {
+    pub(crate) fn new(
+        fmt: Box<dyn Layer<Registry> + Send + Sync>,
+        capture: CaptureLayer,
+        filter: Box<dyn Filter<Registry> + Send + Sync>,
+    ) -> Self {
+        Self {
+            fmt,
+            capture,
+            filter,
+        }
+    }
+}
+
+/// The collector a span belongs to, stored in the span's extensions.
+struct Owner {
+    collector: u64,
+    /// Whether the span is enabled by the collector's filter.
+    enabled: bool,
+}
+
+/// Register a route, installing the global subscriber if needed, and return the id of its collector.
+pub(crate) fn register(route: Route) -> u64 {
+    let installed = INSTALL.get_or_init(|| {
+        tracing_subscriber::registry()
+            .with(Router)
+            .try_init()
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = installed {
+        panic!(
+            "failed to install the global collector, another global subscriber is already set: {e}"
+        );
+    }
+    let id = NEXT_COLLECTOR_ID.fetch_add(1, Ordering::Relaxed);
+    ROUTES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert_with(HashMap::new)
+        .insert(id, route);
+    // let the new filter see all callsites that were registered before it
+    tracing::callsite::rebuild_interest_cache();
+    id
+}
+
+/// Remove the route of a collector and unbind the current thread from it.
+pub(crate) fn unregister(id: u64) {
+    if let Some(routes) = ROUTES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+    {
+        routes.remove(&id);
+    }
+    unbind(id);
+}
+
+/// Bind the current thread to a collector, so that events emitted outside of any of its spans are routed to it.
+pub(crate) fn bind(id: u64) {
+    BOUND.with(|bound| bound.borrow_mut().push(id));
+}
+
+pub(crate) fn unbind(id: u64) {
+    BOUND.with(|bound| {
+        let mut bound = bound.borrow_mut();
+        if let Some(pos) = bound.iter().rposition(|bound_id| *bound_id == id) {
+            bound.remove(pos);
+        }
+    });
+}
+
+fn routes() -> RwLockReadGuard<'static, Option<HashMap<u64, Route>>> {
+    ROUTES.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The collector the current thread is bound to, skipping collectors that have been dropped on other threads.
+fn bound_collector(routes: &HashMap<u64, Route>) -> Option<u64> {
+    BOUND.with(|bound| {
+        bound
+            .borrow()
+            .iter()
+            .rev()
+            .find(|id| routes.contains_key(id))
+            .copied()
+    })
+}
+
+fn owner<S>(span: &SpanRef<'_, S>) -> Option<(u64, bool)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    span.extensions()
+        .get::<Owner>()
+        .map(|owner| (owner.collector, owner.enabled))
+}
+
+/// Format the fields of a span that belongs to no collector with the field formatters of all formats, as the
+/// formatter of a collector created later needs them to format the events in the span.
+fn format_unowned_fields(extensions: &mut ExtensionsMut<'_>, attrs: &span::Attributes<'_>) {
+    insert_fields(extensions, DefaultFields::new(), attrs);
+    insert_fields(extensions, Pretty::default(), attrs);
+    insert_fields(extensions, JsonFields::new(), attrs);
+}
+
+fn insert_fields<N>(extensions: &mut ExtensionsMut<'_>, fmt_fields: N, attrs: &span::Attributes<'_>)
+where
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    let mut fields = FormattedFields::<N>::new(String::new());
+    if fmt_fields.format_fields(fields.as_writer(), attrs).is_ok() {
+        extensions.insert(fields);
+    }
+}
+
+/// The layer of the global subscriber, forwarding spans and events to the route of the collector they belong to.
+struct Router;
+
+impl Router {
+    fn with_span_route(
+        &self,
+        id: &span::Id,
+        ctx: &Context<'_, Registry>,
+        f: impl FnOnce(&Route, bool),
+    ) {
+        let Some((collector, enabled)) = ctx.span(id).as_ref().and_then(owner) else {
+            return;
+        };
+        if let Some(route) = routes().as_ref().and_then(|routes| routes.get(&collector)) {
+            f(route, enabled);
+        }
+    }
+}
+
+impl Layer<Registry> for Router {
+    fn register_callsite(
+        &self,
+        metadata: &'static Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        if let Some(routes) = routes().as_ref() {
+            for route in routes.values() {
+                route.filter.callsite_enabled(metadata);
+            }
+        }
+        tracing::subscriber::Interest::sometimes()
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, Registry>) {
+        let Some(span) = ctx.span(id) else { return };
+        let routes = routes();
+        let Some(routes) = routes.as_ref() else {
+            return;
+        };
+        let collector = span
+            .parent()
+            .as_ref()
+            .and_then(owner)
+            .map(|(collector, _)| collector)
+            .or_else(|| bound_collector(routes));
+        let Some((collector, route)) =
+            collector.and_then(|collector| Some((collector, routes.get(&collector)?)))
+        else {
+            format_unowned_fields(&mut span.extensions_mut(), attrs);
+            return;
+        };
+        let enabled = route.filter.enabled(attrs.metadata(), &ctx);
+        span.extensions_mut().insert(Owner { collector, enabled });
+        drop(span);
+
+        route.filter.on_new_span(attrs, id, ctx.clone());
+        // the formatter needs every span's fields, even of disabled spans that are part of an event's scope
+        route.fmt.on_new_span(attrs, id, ctx.clone());
+        if enabled {
+            route.capture.on_new_span(attrs, id, ctx);
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, Registry>) {
+        self.with_span_route(id, &ctx, |route, enabled| {
+            route.filter.on_record(id, values, ctx.clone());
+            route.fmt.on_record(id, values, ctx.clone());
+            if enabled {
+                route.capture.on_record(id, values, ctx.clone());
+            }
+        });
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, Registry>) {
+        self.with_span_route(id, &ctx, |route, enabled| {
+            route.filter.on_enter(id, ctx.clone());
+            if enabled {
+                route.fmt.on_enter(id, ctx.clone());
+                route.capture.on_enter(id, ctx.clone());
+            }
+        });
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, Registry>) {
+        self.with_span_route(id, &ctx, |route, enabled| {
+            route.filter.on_exit(id, ctx.clone());
+            if enabled {
+                route.fmt.on_exit(id, ctx.clone());
+                route.capture.on_exit(id, ctx.clone());
+            }
+        });
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, Registry>) {
+        self.with_span_route(&id, &ctx, |route, enabled| {
+            route.filter.on_close(id.clone(), ctx.clone());
+            if enabled {
+                route.fmt.on_close(id.clone(), ctx.clone());
+                route.capture.on_close(id.clone(), ctx.clone());
+            }
+        });
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, Registry>) {
+        let routes = routes();
+        let Some(routes) = routes.as_ref() else {
+            return;
+        };
+        // an event in a span created before the collector, or by no collector, goes to the bound collector
+        let collector = ctx
+            .event_span(event)
+            .and_then(|span| owner(&span))
+            .map(|(collector, _)| collector)
+            .or_else(|| bound_collector(routes));
+        let Some(route) = collector.and_then(|collector| routes.get(&collector)) else {
+            return;
+        };
+        if route.filter.enabled(event.metadata(), &ctx) && route.filter.event_enabled(event, &ctx) {
+            route.fmt.on_event(event, ctx.clone());
+            route.capture.on_event(event, ctx);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::Install;
+
+pin_project! {
+    /// A future that collects the traces emitted while it is polled into a
+    /// [`TracingCollector`](crate::TracingCollector). Created with
+    /// [`TracingCollector::instrument`](crate::TracingCollector::instrument).
+    pub struct Instrumented<F> {
+        #[pin]
+        inner: F,
+        install: Option<Install>,
+    }
+}
+
+impl<F> Instrumented<F> {
+    pub(crate) fn new(inner: F, install: Option<Install>) -> Self {
+        Self { inner, install }
+    }
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _binding = this.install.as_ref().map(Install::bind);
+        this.inner.poll(cx)
+    }
+}
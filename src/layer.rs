@@ -0,0 +1,164 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use tracing::{span, Event, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, registry::SpanRef, Layer};
+
+use crate::buffer::Buffer;
+use crate::event::{CollectedEvent, FieldValue, FieldVisitor, SpanContext};
+use crate::lock::LockExt;
+use crate::span::{SpanEvent, SpanEventKind};
+use crate::wait::Notify;
+
+static NEXT_SPAN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The process-unique id and the fields of a span, stored in the span's extensions so that they can be
+/// attached to events and span lifecycle events.
+struct SpanData {
+    id: u64,
+    fields: Vec<(String, FieldValue)>,
+}
+
+/// A `Layer` that records every event as a [`CollectedEvent`] and every span lifecycle event as a [`SpanEvent`].
+pub(crate) struct CaptureLayer {
+    events: Arc<Mutex<Buffer<CollectedEvent>>>,
+    spans: Arc<Mutex<Buffer<SpanEvent>>>,
+    notify: Arc<Notify>,
+}
+
+impl CaptureLayer {
+    pub(crate) fn new(
+        events: Arc<Mutex<Buffer<CollectedEvent>>>,
+        spans: Arc<Mutex<Buffer<SpanEvent>>>,
+        notify: Arc<Notify>,
+    ) -> Self {
+        Self {
+            events,
+            spans,
+            notify,
+        }
+    }
+
+    fn push_span_event<S>(
+        &self,
+        kind: SpanEventKind,
+        span: &SpanRef<'_, S>,
+        fields: Vec<(String, FieldValue)>,
+    ) where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let Some(id) = span_id(span) else { return };
+        let event = SpanEvent {
+            kind,
+            id,
+            parent: span.parent().as_ref().and_then(span_id),
+            name: span.name(),
+            target: span.metadata().target(),
+            level: *span.metadata().level(),
+            fields,
+        };
+        self.spans.lock_or_recover().push(event);
+    }
+}
+
+/// The fields of the span, as recorded by the `CaptureLayer` of the subscriber.
+pub(crate) fn span_fields<S>(span: &SpanRef<'_, S>) -> Vec<(String, FieldValue)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    span.extensions()
+        .get::<SpanData>()
+        .map(|data| data.fields.clone())
+        .unwrap_or_default()
+}
+
+fn span_id<S>(span: &SpanRef<'_, S>) -> Option<u64>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    span.extensions().get::<SpanData>().map(|data| data.id)
+}
+
+impl<S> Layer<S> for CaptureLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let fields = {
+            let mut extensions = span.extensions_mut();
+            match extensions.get_mut::<SpanData>() {
+                Some(data) => data.fields.clone(),
+                None => {
+                    let mut fields = vec![];
+                    attrs.record(&mut FieldVisitor::new(&mut fields));
+                    extensions.insert(SpanData {
+                        id: NEXT_SPAN_ID.fetch_add(1, Ordering::Relaxed),
+                        fields: fields.clone(),
+                    });
+                    fields
+                }
+            }
+        };
+        self.push_span_event(SpanEventKind::New, &span, fields);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut recorded = vec![];
+        values.record(&mut FieldVisitor::new(&mut recorded));
+        if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+            values.record(&mut FieldVisitor::new(&mut data.fields));
+        }
+        self.push_span_event(SpanEventKind::Record, &span, recorded);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.push_span_event(SpanEventKind::Enter, &span, vec![]);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            self.push_span_event(SpanEventKind::Exit, &span, vec![]);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            self.push_span_event(SpanEventKind::Close, &span, vec![]);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = vec![];
+        let mut message = None;
+        event.record(&mut FieldVisitor::with_message(&mut fields, &mut message));
+
+        let spans = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| SpanContext {
+                name: span.name(),
+                target: span.metadata().target(),
+                fields: span_fields(&span),
+            })
+            .collect();
+
+        let event = CollectedEvent {
+            level: *metadata.level(),
+            target: metadata.target(),
+            message,
+            fields,
+            file: metadata.file(),
+            line: metadata.line(),
+            spans,
+        };
+        self.events.lock_or_recover().push(event);
+        self.notify.notify();
+    }
+}
@@ -1,23 +1,76 @@
+mod assert;
+mod buffer;
+mod builder;
+mod echo;
+mod error;
+mod event;
+mod global;
+mod instrument;
+mod layer;
+mod location;
+mod lock;
+mod matcher;
+mod prefix;
+mod recorder;
+mod redact;
+mod render;
+mod ring;
+mod shard;
+mod snapshot;
+mod span;
+mod wait;
+
+use buffer::Buffer;
+use echo::EchoTarget;
+use location::LocationRewriter;
+use lock::LockExt;
+use shard::Shards;
 use std::{
     fmt::{self},
+    future::Future,
     io::{self},
-    mem,
-    sync::{Mutex, MutexGuard},
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
 };
-use tracing::{subscriber::DefaultGuard, Level};
-use tracing_subscriber::fmt::MakeWriter;
-use tracing_subscriber::util::SubscriberInitExt;
+use tracing::{subscriber::DefaultGuard, Dispatch, Level, Metadata, Subscriber};
+use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan, Layer};
+use wait::Notify;
+
+pub use buffer::Checkpoint;
+pub use builder::TracingCollectorBuilder;
+pub use echo::Echo;
+pub use error::CollectorError;
+pub use event::{CollectedEvent, FieldValue, SpanContext};
+pub use instrument::Instrumented;
+pub use location::Locations;
+pub use matcher::EventMatcher;
+pub use prefix::Prefix;
+pub use recorder::{FlightRecorder, FlightRecorderBuilder, FlightRecorderLayer};
+pub use redact::Redaction;
+pub use render::Render;
+pub use span::{SpanEvent, SpanEventKind, SpanTree};
+pub use tracing_subscriber::fmt::format::FmtSpan;
+pub use wait::EventStream;
+
+#[cfg(feature = "macros")]
+pub use tracing_collector_macros::test;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use tracing::Level;
+}
 
 /// `TracingCollector` creates a tracing subscriber that collects a copy of all traces into a buffer.
 /// These traces can be retrieved by calling its Display implementation, i.e. calling `log.to_string()` or `format!("{log}")`.
-/// This is useful for testing with [insta](https://crates.io/crates/insta) snapshots.
+/// This is useful for testing with [insta](https://crates.io/crates/insta) snapshots. The format, filter and limits
+/// of the collected traces are configured with `TracingCollector::builder()`.
 ///
 /// IMPORTANT! `TracingCollector` is meant for use when testing. It collects logs into a memory buffer
-/// which keeps growing until it is read, the program exits or it is dropped. This means that if you are using `TracingCollector`
-/// in production the program will eventually run out of memory.
+/// which keeps growing until it is read, the program exits or it is dropped, unless it is bounded with the builder's
+/// `with_max_events` or `with_max_bytes`. For production, use a [`FlightRecorder`] instead.
 ///
-/// When the `TracingCollector` is dropped, the buffer is emptied and the tracing subscriber is released but
-/// the memory equivalent of a Mutex and an empty Vec<u8> is leaked.
+/// When the `TracingCollector` is dropped, the tracing subscriber is released and the buffer is freed.
 ///
 /// When reading the traces, they are stripped of ANSI escape codes and prefixed with a `㏒` character. The former allows
 /// the use of colored & formatted terminal output when the test fails or is run with `--nocapture` and the latter
@@ -27,163 +80,445 @@ use tracing_subscriber::util::SubscriberInitExt;
 /// Example:
 ///
 /// ```rust
-/// #[test]
-/// fn test_logs() {
-///     let log = TracingCollector::init_debug_level();
-///     tracing::info!("First log");
-///
-///     insta::assert_display_snapshot!(log, @r###"
-///     ㏒   INFO  First log
-///         at tests/test.rs:6
+/// use tracing_collector::TracingCollector;
 ///
-///     "###);
+/// let log = TracingCollector::init_debug_level();
+/// tracing::info!("First log");
 ///
-///     tracing::debug!("Second log");
-///     tracing::info!("Third log");
+/// assert!(log.to_string().starts_with("㏒   INFO  First log\n"));
 ///
-///     insta::assert_display_snapshot!(log, @r###"
-///     ㏒  DEBUG  Second log
-///         at tests/test.rs:14
+/// tracing::debug!("Second log");
+/// tracing::info!("Third log");
 ///
-///       INFO  Third log
-///        at tests/test.rs:15
-///
-///    "###);
-///}
+/// // the first log was consumed by the previous read
+/// let text = log.to_string();
+/// assert!(text.starts_with("㏒  DEBUG  Second log\n"));
+/// assert!(text.contains("  INFO  Third log\n"));
+/// assert!(!text.contains("First log"));
 /// ```
-pub struct TracingCollector {
-    buf: &'static Mutex<Vec<u8>>,
+pub struct TracingCollector<M = Installed> {
+    buf: Arc<Mutex<Buffer<u8>>>,
+    /// The per-thread buffers of the text, merged into `buf` when it is read, if enabled.
+    shards: Option<Arc<Shards>>,
+    events: Arc<Mutex<Buffer<CollectedEvent>>>,
+    spans: Arc<Mutex<Buffer<SpanEvent>>>,
+    notify: Arc<Notify>,
     trace_guard: Mutex<Option<DefaultGuard>>,
-    prefix: Option<char>,
+    install: Option<Install>,
+    echo: EchoTarget,
+    prefix: Option<Prefix>,
+    redactions: Vec<Redaction>,
+    locations: LocationRewriter,
+    mode: PhantomData<M>,
+}
+
+/// The mode of a [`TracingCollector`] that installed its own subscriber, which can be bound to other threads and
+/// futures with `bind` and `instrument`. This is the default.
+pub enum Installed {}
+
+/// The mode of a [`TracingCollector`] created with [`TracingCollector::layer`] or
+/// [`TracingCollectorBuilder::build_layer`]. It only reads the traces collected by its layer, in the subscriber it
+/// was added to, so it can't be bound to other threads and futures.
+pub enum Layered {}
+
+/// How the subscriber of a `TracingCollector` is installed.
+#[derive(Clone)]
+enum Install {
+    /// As the default subscriber of the thread that created the collector.
+    Thread(Dispatch),
+    /// As a route of the process-wide subscriber, identified by the collector id.
+    Global(u64),
 }
 
 impl TracingCollector {
+    /// Create a `TracingCollector` that collects traces up to the `TRACE` level.
+    pub fn init_trace_level() -> Self {
+        Self::init(Level::TRACE)
+    }
+
+    /// Create a `TracingCollector` that collects traces up to the `DEBUG` level.
+    pub fn init_debug_level() -> Self {
+        Self::init(Level::DEBUG)
+    }
+
+    /// Create a `TracingCollector` that collects traces up to the `INFO` level.
+    pub fn init_info_level() -> Self {
+        Self::init(Level::INFO)
+    }
+
+    /// Create a new `TracingCollector` that collects traces up to the specified level.
+    pub fn init(max_level: Level) -> Self {
+        Self::builder().with_max_level(max_level).init()
+    }
+
+    /// Create a new `TracingCollector` that collects traces according to `EnvFilter` directives,
+    /// e.g. `"my_crate=trace,hyper=warn"`.
+    pub fn init_with_env_filter(directives: &str) -> Self {
+        Self::builder().with_env_filter(directives).init()
+    }
+
+    /// Create a `Layer` that collects traces up to the `TRACE` level, to add to an existing subscriber, e.g. one with
+    /// OpenTelemetry or custom layers, and the `TracingCollector` to read them from. Nothing is installed, so the
+    /// subscriber needs to be set as usual, and the collector has no `bind` or `instrument`. Use
+    /// `TracingCollector::builder().build_layer()` to configure it.
+    pub fn layer<S>() -> (Box<dyn Layer<S> + Send + Sync>, TracingCollector<Layered>)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        Self::builder().build_layer()
+    }
+
+    /// Create a `TracingCollectorBuilder` for configuring the format of the collected traces.
+    pub fn builder() -> TracingCollectorBuilder {
+        TracingCollectorBuilder::default()
+    }
+
+    /// Collect the traces emitted on the current thread until the returned guard is dropped.
+    ///
+    /// Use this in threads spawned by a test, which otherwise don't use the collector's subscriber.
+    pub fn bind(&self) -> BindGuard {
+        BindGuard {
+            _binding: self.install.as_ref().map(Install::bind),
+        }
+    }
+
+    /// Wrap a future so that the traces emitted while it is polled are collected, on whichever thread it is polled.
+    ///
+    /// This makes it possible to collect the traces of a future running on a multi-threaded runtime, e.g. in a
+    /// `#[tokio::test(flavor = "multi_thread")]`. Tasks spawned by the future need to be wrapped as well.
+    pub fn instrument<F: Future>(&self, future: F) -> Instrumented<F> {
+        Instrumented::new(future, self.install.clone())
+    }
+}
+
+impl<M> TracingCollector<M> {
     fn new() -> Self {
         TracingCollector {
-            buf: Box::leak(Box::new(Mutex::new(vec![]))),
+            buf: Arc::new(Mutex::new(Buffer::default())),
+            shards: None,
+            events: Arc::new(Mutex::new(Buffer::default())),
+            spans: Arc::new(Mutex::new(Buffer::default())),
+            notify: Arc::new(Notify::default()),
             trace_guard: Mutex::new(None),
-            prefix: Some('㏒'),
+            install: None,
+            echo: EchoTarget::Off,
+            prefix: Some(Prefix::FirstLine("㏒".to_string())),
+            redactions: vec![],
+            locations: LocationRewriter::default(),
+            mode: PhantomData,
         }
     }
 
-    pub fn set_prefix(&mut self, prefix: char) {
-        self.prefix = Some(prefix);
+    /// Set how the collected traces are prefixed when they are read, e.g. `log.set_prefix("> ")` for the first line
+    /// or `log.set_prefix(Prefix::EachLine("| ".into()))` for every line. See [`Prefix`].
+    pub fn set_prefix(&mut self, prefix: impl Into<Prefix>) {
+        self.prefix = Some(prefix.into());
     }
 
     pub fn remove_prefix(&mut self) {
         self.prefix = None;
     }
 
+    /// Set how the collected traces are prefixed when they are read, like `set_prefix` but chainable, e.g.
+    /// `TracingCollector::init_debug_level().with_prefix("> ")`.
+    pub fn with_prefix(mut self, prefix: impl Into<Prefix>) -> Self {
+        self.set_prefix(prefix);
+        self
+    }
+
+    /// Don't prefix the collected traces when they are read, like `remove_prefix` but chainable.
+    pub fn without_prefix(mut self) -> Self {
+        self.remove_prefix();
+        self
+    }
+
+    /// Apply the redaction to the collected traces when they are read, after the ones already added.
+    pub fn add_redaction(&mut self, redaction: Redaction) {
+        self.redactions.push(redaction);
+    }
+
     fn set_guard(&self, trace_guard: DefaultGuard) {
-        let mut guard = self.trace_guard.lock().expect("failed to lock mutex");
+        let mut guard = self.trace_guard.lock_or_recover();
         *guard = Some(trace_guard);
     }
 
-    /// Create a `TracingCollector` that collects traces up to the `TRACE` level.
-    pub fn init_trace_level() -> Self {
-        Self::init(Level::TRACE)
+    pub fn clear(&self) {
+        self.text().clear();
+        self.events.lock_or_recover().clear();
+        self.spans.lock_or_recover().clear();
     }
 
-    /// Create a `TracingCollector` that collects traces up to the `DEBUG` level.
-    pub fn init_debug_level() -> Self {
-        Self::init(Level::DEBUG)
+    /// Get the collected traces without consuming them.
+    pub fn peek(&self) -> String {
+        let buf = self.text();
+        self.render_text(buf.items(), self.prefix.as_ref())
     }
 
-    /// Create a `TracingCollector` that collects traces up to the `INFO` level.
-    pub fn init_info_level() -> Self {
-        Self::init(Level::INFO)
+    /// Get the collected traces and consume them, like the `Display` implementation does.
+    pub fn take(&self) -> String {
+        let buf = self.text().take();
+        self.render_text(&buf, self.prefix.as_ref())
     }
 
-    /// Create a new `TracingCollector` that collects traces up to the specified level.
-    pub fn init(max_level: Level) -> Self {
-        let collector = TracingCollector::new();
+    /// Get the collected traces and consume them, like `take`, but fail instead of recovering when a thread
+    /// panicked while holding the traces' lock or when they aren't valid UTF-8. The traces are not consumed when
+    /// reading them fails.
+    pub fn try_read(&self) -> Result<String, CollectorError> {
+        let mut buf = self.buf.lock().map_err(|_| CollectorError::Poisoned)?;
+        if let Some(shards) = &self.shards {
+            shards.merge_into(&mut buf);
+        }
+        let cleaned = String::from_utf8(strip_ansi_escapes(buf.items()))
+            .map_err(CollectorError::InvalidUtf8)?;
+        buf.take();
+        Ok(self.finish_text(cleaned, self.prefix.as_ref()))
+    }
+
+    /// Save the current position in the collected traces and events, for use with `since` and `events_since`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            text: self.text().end(),
+            events: self.events.lock_or_recover().end(),
+        }
+    }
 
-        let saver = CollectingWriter::new(&collector.buf);
-        let guard = tracing_subscriber::fmt()
-            .pretty()
-            .with_max_level(max_level)
-            .without_time()
-            .with_file(true)
-            .with_line_number(true)
-            .with_target(false)
-            .with_ansi(true)
-            .with_writer(saver)
-            .finish()
-            .set_default();
+    /// Get the traces collected after the checkpoint, without consuming them.
+    ///
+    /// If traces after the checkpoint were already consumed, only the remaining traces are returned.
+    pub fn since(&self, checkpoint: Checkpoint) -> String {
+        let buf = self.text();
+        self.render_text(buf.since(checkpoint.text), self.prefix.as_ref())
+    }
 
-        collector.set_guard(guard);
-        collector
+    /// Get a copy of the structured events collected since the collector was created or they were last
+    /// taken or cleared.
+    ///
+    /// Unlike the `Display` implementation, this does not consume the collected events.
+    pub fn events(&self) -> Vec<CollectedEvent> {
+        self.events.lock_or_recover().items().to_vec()
     }
 
-    pub fn clear(&self) {
-        self.buf.lock().expect("failed to lock mutex").clear();
+    /// Get the structured events and consume them, so that the next call only returns the events collected since.
+    pub fn take_events(&self) -> Vec<CollectedEvent> {
+        self.events.lock_or_recover().take()
+    }
+
+    /// Get a copy of the structured events collected after the checkpoint, without consuming them.
+    pub fn events_since(&self, checkpoint: Checkpoint) -> Vec<CollectedEvent> {
+        self.events
+            .lock_or_recover()
+            .since(checkpoint.events)
+            .to_vec()
+    }
+
+    /// Get a copy of the structured events that match, e.g. `log.events_matching(Level::WARN)`, without consuming them.
+    pub fn events_matching(&self, matcher: impl Into<EventMatcher>) -> Vec<CollectedEvent> {
+        let matcher = matcher.into();
+        self.events
+            .lock_or_recover()
+            .items()
+            .iter()
+            .filter(|event| matcher.matches(event))
+            .cloned()
+            .collect()
+    }
+
+    /// Get the structured events collected since the collector was created or they were last taken or cleared as JSON values,
+    /// using the stable schema described in [`CollectedEvent::to_json`].
+    ///
+    /// This makes it possible to use insta's `assert_json_snapshot!` with redactions on the collected events.
+    /// Like `events()`, this does not consume the collected events.
+    pub fn json_events(&self) -> Vec<serde_json::Value> {
+        self.events
+            .lock_or_recover()
+            .items()
+            .iter()
+            .map(CollectedEvent::to_json)
+            .collect()
+    }
+
+    /// The number of events that were evicted to stay within the builder's `with_max_events` or `with_max_bytes`,
+    /// from the structured events or, if more of them were evicted from it, from the text.
+    pub fn dropped_events(&self) -> usize {
+        let text = self.text().dropped_events();
+        text.max(self.events.lock_or_recover().dropped_events())
+    }
+
+    /// The number of bytes of text that were evicted to stay within the builder's `with_max_bytes` or
+    /// `with_max_events`.
+    pub fn dropped_bytes(&self) -> usize {
+        self.text().dropped()
+    }
+
+    /// Get a copy of the span lifecycle events collected since the collector was created or last cleared.
+    pub fn span_events(&self) -> Vec<SpanEvent> {
+        self.spans.lock_or_recover().items().to_vec()
+    }
+
+    /// Render the spans collected since the collector was created or last cleared as an indented tree, e.g. to
+    /// snapshot the instrumentation of `#[instrument]`ed functions.
+    pub fn span_tree(&self) -> SpanTree {
+        SpanTree::new(self.spans.lock_or_recover().items())
+    }
+
+    /// Configure a single read of the collected traces, e.g. `log.render().prefix(None).to_string()` to read them
+    /// without the prefix this time.
+    pub fn render(&self) -> Render<'_, M> {
+        Render::new(self)
+    }
+
+    /// Lock the buffer of the collected text, after merging the per-thread buffers into it if enabled.
+    fn text(&self) -> MutexGuard<'_, Buffer<u8>> {
+        let mut buf = self.buf.lock_or_recover();
+        if let Some(shards) = &self.shards {
+            shards.merge_into(&mut buf);
+        }
+        buf
+    }
+
+    /// Strip the ANSI escape codes from the collected bytes, rewrite the locations, apply the redactions and add
+    /// the prefix. Invalid UTF-8 is replaced with `�`.
+    fn render_text(&self, buf: &[u8], prefix: Option<&Prefix>) -> String {
+        let cleaned = match String::from_utf8(strip_ansi_escapes(buf)) {
+            Ok(cleaned) => cleaned,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        };
+        self.finish_text(cleaned, prefix)
+    }
+
+    /// Rewrite the locations, apply the redactions and add the prefix.
+    fn finish_text(&self, mut cleaned: String, prefix: Option<&Prefix>) -> String {
+        cleaned = self.locations.apply(&cleaned).into_owned();
+        for redaction in &self.redactions {
+            cleaned = redaction.apply(&cleaned).into_owned();
+        }
+        match prefix {
+            Some(prefix) => prefix.apply(cleaned),
+            None => cleaned,
+        }
     }
 }
 
-impl fmt::Display for TracingCollector {
+fn strip_ansi_escapes(buf: &[u8]) -> Vec<u8> {
+    strip_ansi_escapes::strip(buf).expect("failed to strip ansi escapes")
+}
+
+/// Writes the collected traces and consumes them. Use `peek` to read them without consuming them.
+impl<M> fmt::Display for TracingCollector<M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut buf = vec![];
-        let mut guard = self.buf.lock().expect("failed to lock mutex");
-        mem::swap(&mut buf, &mut *guard);
-        let cleaned_buf = strip_ansi_escapes::strip(&*buf).expect("failed to strip ansi escapes");
-        let cleaned = String::from_utf8(cleaned_buf).expect("log contains invalid utf8");
-        if let Some(prefix) = self.prefix {
-            write!(f, "{prefix}{cleaned}",)
-        } else {
-            write!(f, "{cleaned}",)
+        f.write_str(&self.take())
+    }
+}
+
+impl<M> Drop for TracingCollector<M> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.echo.replay();
+        }
+        self.notify.close();
+        if let Some(Install::Global(id)) = self.install {
+            global::unregister(id);
         }
     }
 }
 
-impl Drop for TracingCollector {
+/// Routes the traces emitted on the current thread to a [`TracingCollector`] until it is dropped.
+/// Created with [`TracingCollector::bind`].
+#[must_use = "the thread is unbound when the guard is dropped"]
+pub struct BindGuard {
+    _binding: Option<Binding>,
+}
+
+/// Makes the subscriber of a `TracingCollector` the current one for the current thread until dropped.
+enum Binding {
+    Thread { _guard: DefaultGuard },
+    Global(u64),
+}
+
+impl Install {
+    fn bind(&self) -> Binding {
+        match self {
+            Install::Thread(dispatch) => Binding::Thread {
+                _guard: tracing::dispatcher::set_default(dispatch),
+            },
+            Install::Global(id) => {
+                global::bind(*id);
+                Binding::Global(*id)
+            }
+        }
+    }
+}
+
+impl Drop for Binding {
     fn drop(&mut self) {
-        let mut vec = self.buf.lock().expect("msg");
-        vec.clear();
-        // reduce the size of the vector as much as possible so that the
-        // leaked memory is only the size of a mutex and an empty vector
-        vec.shrink_to(0);
+        if let Binding::Global(id) = self {
+            global::unbind(*id);
+        }
     }
 }
 
-struct CollectingWriter<'a> {
-    buf: &'a Mutex<Vec<u8>>,
+struct CollectingWriter {
+    buf: Arc<Mutex<Buffer<u8>>>,
+    shards: Option<Arc<Shards>>,
+    echo: EchoTarget,
+    /// Whether the writer is for an event, rather than a span lifecycle event (see `FmtSpan`), which doesn't
+    /// count toward the builder's `with_max_events`.
+    is_event: bool,
 }
 
-impl<'a> CollectingWriter<'a> {
-    /// Create a new `CollectingWriter` that writes into the specified buffer (behind a mutex).
-    fn new(buf: &'a Mutex<Vec<u8>>) -> Self {
-        Self { buf }
+impl CollectingWriter {
+    /// Create a new `CollectingWriter` that writes into the specified buffer (behind a mutex), or into the
+    /// current thread's shard if sharded, and echoes to the specified target.
+    fn new(buf: Arc<Mutex<Buffer<u8>>>, shards: Option<Arc<Shards>>, echo: EchoTarget) -> Self {
+        Self {
+            buf,
+            shards,
+            echo,
+            is_event: true,
+        }
     }
 
-    /// Give access to the internal buffer (behind a `MutexGuard`).
-    fn buf(&self) -> io::Result<MutexGuard<'a, Vec<u8>>> {
-        // Note: The `lock` will block. This would be a problem in production code,
-        // but is fine in tests.
-        self.buf
-            .lock()
-            .map_err(|_| io::Error::from(io::ErrorKind::Other))
+    /// Give access to the internal buffer (behind a `MutexGuard`), even if a thread panicked while holding it.
+    fn buf(&self) -> MutexGuard<'_, Buffer<u8>> {
+        // Note: The `lock` will block, and all threads writing to the collector contend on it. Use
+        // `with_sharded_writer` for high-volume tests.
+        self.buf.lock_or_recover()
     }
 }
 
-impl<'a> io::Write for CollectingWriter<'a> {
+impl io::Write for CollectingWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let string = String::from_utf8(buf.to_vec()).expect("log contains invalid utf8");
-        println!("{string}");
+        self.echo.write(buf, self.is_event);
+        if let Some(shards) = &self.shards {
+            if shards.write(buf, self.is_event) {
+                shards.merge_into(&mut self.buf());
+            }
+            return Ok(buf.len());
+        }
         // Lock target buffer
-        let mut target = self.buf()?;
+        let mut target = self.buf();
         // Write to buffer
-        target.write(buf)
+        target.extend_from_slice(buf, self.is_event);
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.buf()?.flush()
+        Ok(())
     }
 }
 
-impl<'a> MakeWriter<'_> for CollectingWriter<'a> {
+impl MakeWriter<'_> for CollectingWriter {
     type Writer = Self;
 
     fn make_writer(&self) -> Self::Writer {
-        CollectingWriter::new(self.buf)
+        CollectingWriter::new(self.buf.clone(), self.shards.clone(), self.echo.clone())
+    }
+
+    fn make_writer_for(&self, meta: &Metadata<'_>) -> Self::Writer {
+        // the span lifecycle events are formatted as events with the span's metadata
+        CollectingWriter {
+            is_event: !meta.is_span(),
+            ..self.make_writer()
+        }
     }
 }
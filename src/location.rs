@@ -0,0 +1,182 @@
+use regex::{Captures, Regex};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::lock::LockExt;
+
+/// How the source locations (`at tests/test.rs:14`) are shown when reading the collected traces.
+///
+/// Line numbers change with every edit above a log statement, which breaks the inline snapshots of the following
+/// tests. Set with the builder's `with_locations`. Only the locations written by the formatter are rewritten, paths
+/// in the messages and fields are left as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locations {
+    /// Show the path and line number, e.g. `tests/test.rs:14` (the default).
+    #[default]
+    Full,
+    /// Show the path without the line number, e.g. `tests/test.rs`.
+    HideLineNumbers,
+    /// Show the file name only, without the directories and the line number, e.g. `test.rs`.
+    FileName,
+    /// Replace the line numbers with their order of appearance in each file, e.g. `tests/test.rs:#2` for the
+    /// second location of the file that appears in the collected traces.
+    Ordinal,
+}
+
+/// How the formatter writes the source locations, so that only those are rewritten and not paths that appear in
+/// the messages or fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum LocationSyntax {
+    /// The locations are not shown.
+    #[default]
+    None,
+    /// `    at tests/test.rs:14` on its own line after the message, as in the pretty format.
+    Pretty,
+    /// `tests/test.rs:14: ` before the message, as in the compact and full formats.
+    Line,
+    /// `"filename":"tests/test.rs","line_number":14`, as in the JSON format.
+    Json,
+}
+
+/// Rewrites the source locations in the collected traces according to [`Locations`], and normalizes their paths.
+#[derive(Debug, Default)]
+pub(crate) struct LocationRewriter {
+    mode: Locations,
+    normalize: bool,
+    syntax: LocationSyntax,
+    /// The line numbers of each file in the order they appeared, for `Locations::Ordinal`.
+    ordinals: Mutex<HashMap<String, Vec<u32>>>,
+}
+
+/// Matches the locations written with the syntax. The text around the path and the line number is captured as
+/// `pre` and `post` to be kept as is.
+fn location_regex(syntax: LocationSyntax) -> Option<&'static Regex> {
+    static PRETTY: OnceLock<Regex> = OnceLock::new();
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    static JSON: OnceLock<Regex> = OnceLock::new();
+    let (regex, pattern) = match syntax {
+        LocationSyntax::None => return None,
+        LocationSyntax::Pretty => (
+            &PRETTY,
+            r"(?m)^(?P<pre>\s+at )(?P<path>\S+\.rs)(?::(?P<line>\d+))?(?P<post>)",
+        ),
+        // the first `path:line: ` of the line, after the level, thread, spans and target
+        LocationSyntax::Line => (
+            &LINE,
+            r#"(?m)^(?P<pre>[^\n]*? )(?P<path>[^\s"]+\.rs)(?::(?P<line>\d+))?(?P<post>:\s)"#,
+        ),
+        LocationSyntax::Json => (
+            &JSON,
+            r#"(?P<pre>"filename":")(?P<path>[^"]+)"(?:,"line_number":(?P<line>\d+))?(?P<post>)"#,
+        ),
+    };
+    Some(regex.get_or_init(|| Regex::new(pattern).expect("invalid location regex")))
+}
+
+impl LocationRewriter {
+    pub(crate) fn new(mode: Locations, normalize: bool, syntax: LocationSyntax) -> Self {
+        Self {
+            mode,
+            normalize,
+            syntax,
+            ordinals: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(regex) = location_regex(self.syntax) else {
+            return Cow::Borrowed(text);
+        };
+        if self.mode == Locations::Full && !self.normalize {
+            return Cow::Borrowed(text);
+        }
+        let json = self.syntax == LocationSyntax::Json;
+        regex.replace_all(text, |caps: &Captures| {
+            let path = if self.normalize {
+                normalize_path(&caps["path"])
+            } else {
+                Cow::Borrowed(&caps["path"])
+            };
+            let line = caps
+                .name("line")
+                .and_then(|line| line.as_str().parse::<u32>().ok());
+            let (path, line) = match self.mode {
+                Locations::Full => (path, line.map(|line| line.to_string())),
+                Locations::HideLineNumbers => (path, None),
+                Locations::FileName => (file_name(path), None),
+                Locations::Ordinal => {
+                    let ordinal = line.map(|line| format!("#{}", self.ordinal(&path, line)));
+                    (path, ordinal)
+                }
+            };
+            let (pre, post) = (&caps["pre"], &caps["post"]);
+            match (json, line) {
+                (true, Some(line)) if self.mode == Locations::Ordinal => {
+                    format!(r#"{pre}{path}","line_number":"{line}"{post}"#)
+                }
+                (true, Some(line)) => format!(r#"{pre}{path}","line_number":{line}{post}"#),
+                (true, None) => format!(r#"{pre}{path}"{post}"#),
+                (false, Some(line)) => format!("{pre}{path}:{line}{post}"),
+                (false, None) => format!("{pre}{path}{post}"),
+            }
+        })
+    }
+
+    /// The 1-based order in which the line of the file first appeared.
+    fn ordinal(&self, path: &str, line: u32) -> usize {
+        let mut ordinals = self.ordinals.lock_or_recover();
+        let lines = ordinals.entry(path.to_string()).or_default();
+        match lines.iter().position(|&seen| seen == line) {
+            Some(index) => index + 1,
+            None => {
+                lines.push(line);
+                lines.len()
+            }
+        }
+    }
+}
+
+fn file_name(path: Cow<'_, str>) -> Cow<'_, str> {
+    match path.rfind(['/', '\\']) {
+        Some(index) => Cow::Owned(path[index + 1..].to_string()),
+        None => path,
+    }
+}
+
+/// Make the path relative to the package's directory, whether it is absolute (e.g. under `cargo llvm-cov`) or
+/// relative to the workspace's directory, and use `/` as separator.
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    let Some(package_dir) = package_dir() else {
+        return Cow::Borrowed(path);
+    };
+    let relative = Path::new(path).strip_prefix(package_dir).ok().or_else(|| {
+        // A relative path starts with the package's directory relative to one of its ancestors,
+        // e.g. `crates/foo/tests/test.rs` for the package in `/workspace/crates/foo`.
+        package_dir
+            .ancestors()
+            .skip(1)
+            .filter_map(|ancestor| package_dir.strip_prefix(ancestor).ok())
+            .find_map(|package| Path::new(path).strip_prefix(package).ok())
+    });
+    match relative {
+        Some(relative) => Cow::Owned(relative.to_string_lossy().replace('\\', "/")),
+        None if path.contains('\\') => Cow::Owned(path.replace('\\', "/")),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// The directory of the package being tested, as set by cargo when running tests.
+fn package_dir() -> Option<&'static Path> {
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| {
+        env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .or_else(|| env::current_dir().ok())
+    })
+    .as_deref()
+}
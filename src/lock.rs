@@ -0,0 +1,16 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Locking that recovers from poisoning.
+///
+/// A mutex is poisoned when a thread panics while holding it, e.g. in a matcher's predicate or in a test that
+/// failed in another thread. The collected data is still consistent since it is only modified by the crate, so the
+/// lock is taken anyway rather than cascading the panic into every later read.
+pub(crate) trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
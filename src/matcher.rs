@@ -0,0 +1,308 @@
+use regex::Regex;
+use std::{fmt, sync::Arc};
+use tracing::Level;
+
+use crate::event::{CollectedEvent, FieldValue};
+
+/// Describes the [`CollectedEvent`]s to look for, e.g. with the assertions of a
+/// [`TracingCollector`](crate::TracingCollector) or `log.events_matching(matcher)`. An event matches when it
+/// satisfies all conditions.
+///
+/// Matchers compose with `and`, `or` and `EventMatcher::not`, so that shared matchers can be built once and reused
+/// across test suites.
+///
+/// Example:
+///
+/// ```rust
+/// use tracing::Level;
+/// use tracing_collector::{EventMatcher, TracingCollector};
+///
+/// fn database_error() -> EventMatcher {
+///     EventMatcher::new().level(Level::ERROR).target("*::db*")
+/// }
+///
+/// let log = TracingCollector::init_debug_level();
+/// tracing::warn!(attempt = 3, "retrying upload");
+///
+/// log.assert_contains(
+///     EventMatcher::new()
+///         .level(Level::WARN)
+///         .message_matches("^retrying")
+///         .field_matches("attempt", |value| value.matches(&3.into())),
+/// );
+/// log.assert_not_logged(database_error().or(Level::ERROR));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventMatcher {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone)]
+enum Condition {
+    Level(Level),
+    Target(String),
+    MessageContains(String),
+    MessageMatches(Regex),
+    Field(String, FieldValue),
+    HasField(String),
+    FieldMatches(String, Arc<dyn Fn(&FieldValue) -> bool + Send + Sync>),
+    ParentSpan(String),
+    InSpan(String),
+    Any(Vec<EventMatcher>),
+    Not(Box<EventMatcher>),
+}
+
+impl EventMatcher {
+    /// Create a matcher without conditions, which matches any event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match events with the level.
+    pub fn level(self, level: Level) -> Self {
+        self.with(Condition::Level(level))
+    }
+
+    /// Match events whose target matches the glob pattern, where `*` matches any characters and `?` matches one
+    /// character, e.g. `"my_crate::db*"`.
+    pub fn target(self, pattern: impl Into<String>) -> Self {
+        self.with(Condition::Target(pattern.into()))
+    }
+
+    /// Match events whose message contains the text.
+    pub fn message_contains(self, text: impl Into<String>) -> Self {
+        self.with(Condition::MessageContains(text.into()))
+    }
+
+    /// Match events whose message matches the regular expression.
+    ///
+    /// Panics if the regular expression is invalid.
+    #[track_caller]
+    pub fn message_matches(self, regex: &str) -> Self {
+        let regex = Regex::new(regex).unwrap_or_else(|err| panic!("invalid regex: {err}"));
+        self.with(Condition::MessageMatches(regex))
+    }
+
+    /// Match events with a field with the value, see [`FieldValue::matches`].
+    pub fn field(self, name: impl Into<String>, value: impl Into<FieldValue>) -> Self {
+        self.with(Condition::Field(name.into(), value.into()))
+    }
+
+    /// Match events with a field with the name, whatever its value.
+    pub fn has_field(self, name: impl Into<String>) -> Self {
+        self.with(Condition::HasField(name.into()))
+    }
+
+    /// Match events with a field whose value satisfies the predicate.
+    pub fn field_matches<F>(self, name: impl Into<String>, predicate: F) -> Self
+    where
+        F: Fn(&FieldValue) -> bool + Send + Sync + 'static,
+    {
+        self.with(Condition::FieldMatches(name.into(), Arc::new(predicate)))
+    }
+
+    /// Match events emitted directly in a span with the name.
+    pub fn parent_span(self, name: impl Into<String>) -> Self {
+        self.with(Condition::ParentSpan(name.into()))
+    }
+
+    /// Match events emitted in a span with the name, or in one of its descendants.
+    pub fn in_span(self, name: impl Into<String>) -> Self {
+        self.with(Condition::InSpan(name.into()))
+    }
+
+    /// Match events that match both this matcher and the other one.
+    pub fn and(mut self, other: impl Into<EventMatcher>) -> Self {
+        self.conditions.extend(other.into().conditions);
+        self
+    }
+
+    /// Match events that match this matcher or the other one.
+    pub fn or(self, other: impl Into<EventMatcher>) -> Self {
+        EventMatcher::new().with(Condition::Any(vec![self, other.into()]))
+    }
+
+    /// Match events that don't match the matcher, e.g. `EventMatcher::not(Level::TRACE)`.
+    pub fn not(matcher: impl Into<EventMatcher>) -> Self {
+        EventMatcher::new().with(Condition::Not(Box::new(matcher.into())))
+    }
+
+    fn with(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Whether the event satisfies all conditions.
+    pub fn matches(&self, event: &CollectedEvent) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(event))
+    }
+
+    /// The number of conditions the event satisfies, used to find the closest event when an assertion fails.
+    pub(crate) fn score(&self, event: &CollectedEvent) -> usize {
+        self.conditions
+            .iter()
+            .filter(|condition| condition.matches(event))
+            .count()
+    }
+
+    /// Describe how the event differs from the matcher, with a `-` line for each condition the event doesn't
+    /// satisfy followed by a `+` line with the event's actual value.
+    pub(crate) fn diff(&self, event: &CollectedEvent) -> String {
+        let mut diff = String::new();
+        for condition in &self.conditions {
+            if !condition.matches(event) {
+                diff.push_str(&format!(
+                    "  - {condition}\n  + {}\n",
+                    condition.actual(event)
+                ));
+            }
+        }
+        diff
+    }
+}
+
+impl Condition {
+    fn matches(&self, event: &CollectedEvent) -> bool {
+        match self {
+            Condition::Level(level) => event.level == *level,
+            Condition::Target(pattern) => glob_match(pattern, event.target),
+            Condition::MessageContains(text) => event
+                .message
+                .as_deref()
+                .is_some_and(|message| message.contains(text.as_str())),
+            Condition::MessageMatches(regex) => event
+                .message
+                .as_deref()
+                .is_some_and(|message| regex.is_match(message)),
+            Condition::Field(name, value) => event
+                .field(name)
+                .is_some_and(|actual| actual.matches(value)),
+            Condition::HasField(name) => event.field(name).is_some(),
+            Condition::FieldMatches(name, predicate) => event.field(name).is_some_and(&**predicate),
+            Condition::ParentSpan(name) => event.spans.last().is_some_and(|span| span.name == name),
+            Condition::InSpan(name) => event.spans.iter().any(|span| span.name == name),
+            Condition::Any(matchers) => matchers.iter().any(|matcher| matcher.matches(event)),
+            Condition::Not(matcher) => !matcher.matches(event),
+        }
+    }
+
+    /// Describe the event's value for this condition.
+    fn actual(&self, event: &CollectedEvent) -> String {
+        match self {
+            Condition::Level(_) => format!("level == {}", event.level),
+            Condition::Target(_) => format!("target == {}", event.target),
+            Condition::MessageContains(_) | Condition::MessageMatches(_) => match &event.message {
+                Some(message) => format!("message == {message:?}"),
+                None => "no message".to_string(),
+            },
+            Condition::Field(name, _)
+            | Condition::HasField(name)
+            | Condition::FieldMatches(name, _) => match event.field(name) {
+                Some(value) => format!("{name} == {value}"),
+                None => format!("no field {name}"),
+            },
+            Condition::ParentSpan(_) | Condition::InSpan(_) => {
+                let spans: Vec<_> = event.spans.iter().map(|span| span.name).collect();
+                if spans.is_empty() {
+                    "no span".to_string()
+                } else {
+                    format!("spans == {}", spans.join("::"))
+                }
+            }
+            Condition::Any(_) => "matches none".to_string(),
+            Condition::Not(matcher) => matcher.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Level(level) => write!(f, "level == {level}"),
+            Condition::Target(pattern) => write!(f, "target matches {pattern}"),
+            Condition::MessageContains(text) => write!(f, "message contains {text:?}"),
+            Condition::MessageMatches(regex) => write!(f, "message matches /{regex}/"),
+            Condition::Field(name, value) => write!(f, "{name} == {value}"),
+            Condition::HasField(name) => write!(f, "has field {name}"),
+            Condition::FieldMatches(name, _) => write!(f, "{name} matches predicate"),
+            Condition::ParentSpan(name) => write!(f, "parent span == {name}"),
+            Condition::InSpan(name) => write!(f, "in span {name}"),
+            Condition::Any(matchers) => {
+                for (i, matcher) in matchers.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " or ")?;
+                    }
+                    write!(f, "({matcher})")?;
+                }
+                Ok(())
+            }
+            Condition::Not(matcher) => write!(f, "not ({matcher})"),
+        }
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+/// Writes the conditions separated by commas, e.g. `level == WARN, message contains "retry"`.
+impl fmt::Display for EventMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.conditions.is_empty() {
+            return write!(f, "any event");
+        }
+        for (i, condition) in self.conditions.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{condition}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Level> for EventMatcher {
+    fn from(level: Level) -> Self {
+        EventMatcher::new().level(level)
+    }
+}
+
+impl From<&EventMatcher> for EventMatcher {
+    fn from(matcher: &EventMatcher) -> Self {
+        matcher.clone()
+    }
+}
+
+/// Match the text against a glob pattern where `*` matches any characters and `?` matches one character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` in the pattern and the position in the text it was matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
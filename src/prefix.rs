@@ -0,0 +1,63 @@
+/// How the collected traces are prefixed when they are read. Set with
+/// [`TracingCollector::set_prefix`](crate::TracingCollector::set_prefix).
+///
+/// Insta's inline snapshots strip the indentation common to all lines, which would also strip the leading
+/// whitespace of the traces. A prefix on the first line (`㏒` by default) or on every line prevents this, and
+/// `Dedent` removes the common indentation from the traces themselves so that no prefix is needed.
+///
+/// A `&str`, `String` or `char` converts to a prefix of the first line, e.g. `log.set_prefix("> ")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prefix {
+    /// Prefix the first line.
+    FirstLine(String),
+    /// Prefix every line, e.g. `| `.
+    EachLine(String),
+    /// Remove the indentation common to all lines instead of adding a prefix.
+    Dedent,
+}
+
+impl Prefix {
+    pub(crate) fn apply(&self, text: String) -> String {
+        match self {
+            Prefix::FirstLine(prefix) => format!("{prefix}{text}"),
+            Prefix::EachLine(prefix) => text
+                .split_inclusive('\n')
+                .map(|line| format!("{prefix}{line}"))
+                .collect(),
+            Prefix::Dedent => {
+                let indent = text
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(indentation)
+                    .min()
+                    .unwrap_or(0);
+                text.split_inclusive('\n')
+                    .map(|line| &line[indentation(line).min(indent)..])
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The number of leading spaces and tabs of the line.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+impl From<&str> for Prefix {
+    fn from(prefix: &str) -> Self {
+        Prefix::FirstLine(prefix.to_string())
+    }
+}
+
+impl From<String> for Prefix {
+    fn from(prefix: String) -> Self {
+        Prefix::FirstLine(prefix)
+    }
+}
+
+impl From<char> for Prefix {
+    fn from(prefix: char) -> Self {
+        Prefix::FirstLine(prefix.to_string())
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    cell::RefCell,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, Write},
+    panic,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Once, Weak,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::event::FieldVisitor;
+use crate::lock::LockExt;
+use crate::ring::Ring;
+
+/// The recorders to dump on panic, by the panic hook installed once for all of them.
+static PANIC_RECORDERS: Mutex<Vec<Weak<Inner>>> = Mutex::new(Vec::new());
+static PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    /// The rings of the current thread, for per-thread recorders.
+    static THREAD_RINGS: RefCell<Vec<ThreadRing>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps the last events in memory to dump them when something goes wrong, e.g. on panic, on a signal or on an
+/// `ERROR` event, as a flight recorder for production use. Created with [`FlightRecorder::builder`].
+///
+/// Unlike a [`TracingCollector`](crate::TracingCollector), it is a `Layer` to add to the application's subscriber
+/// and it doesn't block: the events are formatted on one line (`LEVEL target: span:child: message field=value`)
+/// into a fixed-size lock-free ring, either global or per thread. An event is dropped rather than waiting when
+/// another thread is writing the same slot of a global ring.
+///
+/// Example:
+///
+/// ```rust
+/// use tracing_collector::FlightRecorder;
+/// use tracing_subscriber::prelude::*;
+///
+/// let recorder = FlightRecorder::builder()
+///     .with_capacity(256)
+///     .per_thread()
+///     .dump_on_panic()
+///     .dump_on_error()
+///     .build();
+/// let subscriber = tracing_subscriber::registry().with(recorder.layer());
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::info!(target: "app", user = "bob", "Logged in");
+/// });
+///
+/// assert_eq!(recorder.dump(), "INFO app: Logged in user=\"bob\"\n");
+/// ```
+#[derive(Clone)]
+pub struct FlightRecorder {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    capacity: usize,
+    max_line_len: usize,
+    /// The global ring, or the rings of the threads that recorded events.
+    rings: Rings,
+    next_seq: AtomicU64,
+    dump_on_error: bool,
+    error_dump_interval: Duration,
+    created: Instant,
+    /// The time of the last dump on error, in milliseconds since `created` plus one, or 0 if there was none.
+    last_error_dump: AtomicU64,
+    target: DumpTarget,
+}
+
+enum Rings {
+    Global(Ring),
+    /// Only locked when a thread records its first event and when dumping.
+    PerThread {
+        /// All the rings, dumped together.
+        all: Mutex<Vec<Arc<Ring>>>,
+        /// The rings of the threads that exited, reused by new threads so that the number of rings is bounded by
+        /// the number of threads running at the same time. They keep their events until they are overwritten.
+        idle: Mutex<Vec<Arc<Ring>>>,
+    },
+}
+
+/// The ring of a per-thread recorder used by the current thread, made idle when the thread exits.
+struct ThreadRing {
+    recorder: Weak<Inner>,
+    ring: Arc<Ring>,
+}
+
+impl Drop for ThreadRing {
+    fn drop(&mut self) {
+        if let Some(inner) = self.recorder.upgrade() {
+            if let Rings::PerThread { idle, .. } = &inner.rings {
+                idle.lock_or_recover().push(self.ring.clone());
+            }
+        }
+    }
+}
+
+/// Where the recorded events are dumped.
+#[derive(Debug, Clone)]
+enum DumpTarget {
+    Stderr,
+    File(PathBuf),
+}
+
+/// Configures a [`FlightRecorder`]. Created with [`FlightRecorder::builder`].
+#[derive(Debug)]
+pub struct FlightRecorderBuilder {
+    capacity: usize,
+    max_line_len: usize,
+    per_thread: bool,
+    dump_on_panic: bool,
+    dump_on_error: bool,
+    error_dump_interval: Duration,
+    #[cfg(all(unix, feature = "signal"))]
+    dump_on_signals: Vec<i32>,
+    target: DumpTarget,
+}
+
+impl Default for FlightRecorderBuilder {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            max_line_len: 512,
+            per_thread: false,
+            dump_on_panic: false,
+            dump_on_error: false,
+            error_dump_interval: Duration::from_secs(1),
+            #[cfg(all(unix, feature = "signal"))]
+            dump_on_signals: vec![],
+            target: DumpTarget::Stderr,
+        }
+    }
+}
+
+impl FlightRecorderBuilder {
+    /// Keep the last `capacity` events, globally or per thread. Defaults to 1024.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Truncate the recorded lines to `max_line_len` bytes. Defaults to 512.
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    /// Keep the last events of each thread, instead of the last events of all threads. Each thread writes to its
+    /// own ring, so events are never dropped because of other threads.
+    ///
+    /// A ring is allocated when a thread records its first event. When the thread exits, its ring (with its events)
+    /// is reused by the next new thread, so the memory is bounded by the number of threads running at the same
+    /// time, e.g. with a pool of worker threads that are replaced over time.
+    pub fn per_thread(mut self) -> Self {
+        self.per_thread = true;
+        self
+    }
+
+    /// Dump the recorded events when a thread panics, before the previous panic hook runs. A single panic hook is
+    /// installed for all recorders.
+    pub fn dump_on_panic(mut self) -> Self {
+        self.dump_on_panic = true;
+        self
+    }
+
+    /// Dump the recorded events when an `ERROR` event is recorded, at most once per `with_error_dump_interval`.
+    pub fn dump_on_error(mut self) -> Self {
+        self.dump_on_error = true;
+        self
+    }
+
+    /// Dump on `ERROR` events at most once per `interval`, so that a burst of errors is dumped once rather than
+    /// once per error. The errors that follow are in the next dump. Defaults to one second.
+    pub fn with_error_dump_interval(mut self, interval: Duration) -> Self {
+        self.error_dump_interval = interval;
+        self
+    }
+
+    /// Dump the recorded events when the process receives the signal, e.g. `SIGUSR1`. The signal is handled by a
+    /// background thread, so that the dump doesn't run in the signal handler.
+    ///
+    /// Requires the `signal` feature.
+    #[cfg(all(unix, feature = "signal"))]
+    pub fn dump_on_signal(mut self, signal: i32) -> Self {
+        self.dump_on_signals.push(signal);
+        self
+    }
+
+    /// Dump to stderr (the default).
+    pub fn dump_to_stderr(mut self) -> Self {
+        self.target = DumpTarget::Stderr;
+        self
+    }
+
+    /// Append the dumps to the file.
+    pub fn dump_to_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.target = DumpTarget::File(path.into());
+        self
+    }
+
+    /// Create the `FlightRecorder` and install its panic hook and signal handlers.
+    ///
+    /// Panics if a signal handler can't be registered.
+    pub fn build(self) -> FlightRecorder {
+        let rings = if self.per_thread {
+            Rings::PerThread {
+                all: Mutex::new(vec![]),
+                idle: Mutex::new(vec![]),
+            }
+        } else {
+            Rings::Global(Ring::new(self.capacity, self.max_line_len))
+        };
+        let inner = Arc::new(Inner {
+            capacity: self.capacity,
+            max_line_len: self.max_line_len,
+            rings,
+            next_seq: AtomicU64::new(1),
+            dump_on_error: self.dump_on_error,
+            error_dump_interval: self.error_dump_interval,
+            created: Instant::now(),
+            last_error_dump: AtomicU64::new(0),
+            target: self.target,
+        });
+
+        if self.dump_on_panic {
+            let mut recorders = PANIC_RECORDERS.lock_or_recover();
+            recorders.retain(|recorder| recorder.strong_count() > 0);
+            recorders.push(Arc::downgrade(&inner));
+            drop(recorders);
+            PANIC_HOOK.call_once(|| {
+                let previous = panic::take_hook();
+                panic::set_hook(Box::new(move |info| {
+                    let recorders = PANIC_RECORDERS
+                        .lock_or_recover()
+                        .iter()
+                        .filter_map(Weak::upgrade)
+                        .collect::<Vec<_>>();
+                    for inner in recorders {
+                        inner.write_dump("panic");
+                    }
+                    previous(info);
+                }));
+            });
+        }
+
+        #[cfg(all(unix, feature = "signal"))]
+        if !self.dump_on_signals.is_empty() {
+            let mut signals = signal_hook::iterator::Signals::new(&self.dump_on_signals)
+                .expect("failed to register signal handler");
+            let weak = Arc::downgrade(&inner);
+            std::thread::Builder::new()
+                .name("flight-recorder".to_string())
+                .spawn(move || {
+                    for signal in signals.forever() {
+                        match weak.upgrade() {
+                            Some(inner) => inner.write_dump(&format!("signal {signal}")),
+                            None => break,
+                        }
+                    }
+                })
+                .expect("failed to spawn signal thread");
+        }
+
+        FlightRecorder { inner }
+    }
+}
+
+impl FlightRecorder {
+    /// Create a `FlightRecorderBuilder`.
+    pub fn builder() -> FlightRecorderBuilder {
+        FlightRecorderBuilder::default()
+    }
+
+    /// Create a `Layer` recording the events into this recorder, to add to the application's subscriber.
+    pub fn layer(&self) -> FlightRecorderLayer {
+        FlightRecorderLayer {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Get the recorded events, oldest first, one per line.
+    pub fn dump(&self) -> String {
+        self.inner.dump()
+    }
+
+    /// Write the recorded events to the configured target (stderr by default), as done on panic, signal or error.
+    pub fn write_dump(&self) {
+        self.inner.write_dump("request");
+    }
+
+    /// The number of events that were dropped because another thread was writing the same slot of the global ring.
+    pub fn dropped_events(&self) -> u64 {
+        match &self.inner.rings {
+            Rings::Global(ring) => ring.dropped(),
+            // each ring has a single writer
+            Rings::PerThread { .. } => 0,
+        }
+    }
+}
+
+impl Inner {
+    fn record(self: &Arc<Self>, line: &str) {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        match &self.rings {
+            Rings::Global(ring) => ring.push(seq, line),
+            Rings::PerThread { all, idle } => {
+                // the thread-locals are gone when a thread logs while it exits, the event is dropped then
+                let _ = THREAD_RINGS.try_with(|thread_rings| {
+                    let mut thread_rings = thread_rings.borrow_mut();
+                    let ring = match thread_rings
+                        .iter()
+                        .find(|thread_ring| thread_ring.recorder.as_ptr() == Arc::as_ptr(self))
+                    {
+                        Some(thread_ring) => thread_ring.ring.clone(),
+                        None => {
+                            let ring = idle.lock_or_recover().pop().unwrap_or_else(|| {
+                                let ring = Arc::new(Ring::new(self.capacity, self.max_line_len));
+                                all.lock_or_recover().push(ring.clone());
+                                ring
+                            });
+                            // forget the rings of dropped recorders
+                            thread_rings
+                                .retain(|thread_ring| thread_ring.recorder.strong_count() > 0);
+                            thread_rings.push(ThreadRing {
+                                recorder: Arc::downgrade(self),
+                                ring: ring.clone(),
+                            });
+                            ring
+                        }
+                    };
+                    ring.push(seq, line);
+                });
+            }
+        }
+    }
+
+    /// Whether to dump on an `ERROR` event, at most once per `error_dump_interval`.
+    fn error_dump_due(&self) -> bool {
+        let now = self.created.elapsed().as_millis() as u64 + 1;
+        let last = self.last_error_dump.load(Ordering::Relaxed);
+        if last != 0 && now - last < self.error_dump_interval.as_millis() as u64 {
+            return false;
+        }
+        self.last_error_dump
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn dump(&self) -> String {
+        let mut records = match &self.rings {
+            Rings::Global(ring) => ring.read(),
+            Rings::PerThread { all, .. } => all
+                .lock_or_recover()
+                .iter()
+                .flat_map(|ring| ring.read())
+                .collect(),
+        };
+        records.sort_by_key(|(seq, _)| *seq);
+        records
+            .into_iter()
+            .fold(String::new(), |mut dump, (_, line)| {
+                dump.push_str(&line);
+                dump.push('\n');
+                dump
+            })
+    }
+
+    /// Write the dump to the target, best effort since it runs when something already went wrong.
+    fn write_dump(&self, reason: &str) {
+        let dump = format!("---- flight recorder ({reason}) ----\n{}", self.dump());
+        let _ = match &self.target {
+            DumpTarget::Stderr => io::stderr().lock().write_all(dump.as_bytes()),
+            DumpTarget::File(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(dump.as_bytes())),
+        };
+    }
+}
+
+/// The `Layer` recording events into a [`FlightRecorder`]. Created with [`FlightRecorder::layer`].
+pub struct FlightRecorderLayer {
+    inner: Arc<Inner>,
+}
+
+impl<S> Layer<S> for FlightRecorderLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let inner = &self.inner;
+        let metadata = event.metadata();
+        let mut line = format!("{} {}:", metadata.level(), metadata.target());
+        for span in ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+        {
+            let _ = write!(line, " {}:", span.name());
+        }
+        let mut fields = vec![];
+        let mut message = None;
+        event.record(&mut FieldVisitor::with_message(&mut fields, &mut message));
+        if let Some(message) = message {
+            let _ = write!(line, " {message}");
+        }
+        for (name, value) in fields {
+            let _ = write!(line, " {name}={value}");
+        }
+        inner.record(&line);
+
+        if inner.dump_on_error && *metadata.level() == Level::ERROR && inner.error_dump_due() {
+            inner.write_dump("error");
+        }
+    }
+}
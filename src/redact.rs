@@ -0,0 +1,117 @@
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+/// A replacement applied to the collected traces when they are read, to keep snapshots stable when they contain
+/// values that change between runs, like ids, timestamps or temporary paths.
+///
+/// Added with the builder's `with_redaction` or with [`TracingCollector::add_redaction`](crate::TracingCollector::add_redaction),
+/// and applied in the order they were added.
+///
+/// Example:
+///
+/// ```rust
+/// use tracing_collector::{Redaction, TracingCollector};
+///
+/// let log = TracingCollector::builder()
+///     .compact()
+///     .with_file(false)
+///     .with_line_number(false)
+///     .with_redaction(Redaction::uuid())
+///     .with_redaction(Redaction::field("request_id", "[id]"))
+///     .with_redaction(Redaction::regex(r"port \d+", "port [port]"))
+///     .init();
+/// tracing::info!(request_id = 42, "listening on port 8080 as 67e55044-10b1-426f-9247-bb680e5fe0c8");
+///
+/// assert_eq!(log.to_string(), "㏒ INFO listening on port [port] as [uuid] request_id=[id]\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    regex: Regex,
+    replacement: Replacement,
+}
+
+#[derive(Debug, Clone)]
+enum Replacement {
+    /// Replace the match, expanding `$name` and `${name}` capture group references.
+    Expand(String),
+    /// Replace the value of a field (the second group), keeping the field name (the first group) and the quotes.
+    Value(String),
+}
+
+impl Redaction {
+    /// Replace the matches of the regular expression. The replacement can refer to capture groups with `$1` or
+    /// `${name}`, like `Regex::replace_all`.
+    ///
+    /// Panics if the regular expression is invalid.
+    #[track_caller]
+    pub fn regex(regex: &str, replacement: impl Into<String>) -> Self {
+        let regex = Regex::new(regex).unwrap_or_else(|err| panic!("invalid regex: {err}"));
+        Self {
+            regex,
+            replacement: Replacement::Expand(replacement.into()),
+        }
+    }
+
+    /// Replace the value of the field with the name, e.g. `Redaction::field("request_id", "[id]")`, in any of the
+    /// formats of the builder.
+    pub fn field(name: &str, replacement: impl Into<String>) -> Self {
+        let name = regex::escape(name);
+        let regex = Regex::new(&format!(
+            r#"(\b{name}(?:=|: |":))("(?:[^"\\]|\\.)*"|[^\s,}}]+)"#
+        ))
+        .expect("invalid field regex");
+        Self {
+            regex,
+            replacement: Replacement::Value(replacement.into()),
+        }
+    }
+
+    /// Replace UUIDs with `[uuid]`.
+    pub fn uuid() -> Self {
+        Self::regex(
+            r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+            "[uuid]",
+        )
+    }
+
+    /// Replace ISO-8601 date times, e.g. `2024-01-31T12:00:00.123Z`, with `[timestamp]`.
+    pub fn iso8601() -> Self {
+        Self::regex(
+            r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?",
+            "[timestamp]",
+        )
+    }
+
+    /// Replace hexadecimal addresses, e.g. `0x7ffd5e8c`, with `[address]`.
+    pub fn hex_address() -> Self {
+        Self::regex(r"\b0x[0-9a-fA-F]+\b", "[address]")
+    }
+
+    /// Replace paths in `/tmp` or the system's temporary directory with `[tmp]`.
+    pub fn tmp_path() -> Self {
+        let temp_dir = std::env::temp_dir();
+        let temp_dir = regex::escape(temp_dir.to_string_lossy().trim_end_matches(['/', '\\']));
+        Self::regex(
+            &format!(r#"(?:/tmp|{temp_dir})(?:[/\\][^\s"':,;)\]}}]*)?"#),
+            "[tmp]",
+        )
+    }
+
+    /// Replace durations, e.g. `1.5ms` or `20µs` as formatted for `FmtSpan::CLOSE`, with `[duration]`.
+    pub fn duration() -> Self {
+        Self::regex(r"\b\d+(?:\.\d+)?(?:ns|µs|us|ms|s)\b", "[duration]")
+    }
+
+    pub(crate) fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.replacement {
+            Replacement::Expand(replacement) => self.regex.replace_all(text, replacement.as_str()),
+            Replacement::Value(replacement) => self.regex.replace_all(text, |caps: &Captures| {
+                if caps[2].starts_with('"') {
+                    format!("{}\"{replacement}\"", &caps[1])
+                } else {
+                    format!("{}{replacement}", &caps[1])
+                }
+            }),
+        }
+    }
+}
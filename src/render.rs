@@ -0,0 +1,43 @@
+use std::fmt;
+
+use crate::{Installed, Prefix, TracingCollector};
+
+/// A single read of the traces collected by a [`TracingCollector`], with its own options. Created with
+/// [`TracingCollector::render`].
+///
+/// Like the collector's, the `Display` implementation consumes the traces. Use `peek` to read them without
+/// consuming them.
+pub struct Render<'a, M = Installed> {
+    collector: &'a TracingCollector<M>,
+    prefix: Option<Prefix>,
+}
+
+impl<'a, M> Render<'a, M> {
+    pub(crate) fn new(collector: &'a TracingCollector<M>) -> Self {
+        Self {
+            collector,
+            prefix: collector.prefix.clone(),
+        }
+    }
+
+    /// Override the collector's prefix for this read, e.g. `None` for no prefix.
+    pub fn prefix(mut self, prefix: Option<Prefix>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Get the collected traces without consuming them.
+    pub fn peek(&self) -> String {
+        let buf = self.collector.text();
+        self.collector
+            .render_text(buf.items(), self.prefix.as_ref())
+    }
+}
+
+/// Writes the collected traces and consumes them.
+impl<M> fmt::Display for Render<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = self.collector.text().take();
+        f.write_str(&self.collector.render_text(&buf, self.prefix.as_ref()))
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+
+/// A fixed-size ring of text records that can be written and read concurrently without locks.
+///
+/// Each slot is a seqlock: its version is odd while a writer fills it, and readers retry (or skip the slot)
+/// when the version changed while they read it. A writer that finds its slot being written by another one
+/// (because the ring wrapped around) drops its record instead of waiting.
+pub(crate) struct Ring {
+    slots: Box<[Slot]>,
+    head: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+struct Slot {
+    version: AtomicU64,
+    /// The sequence number of the record, 0 when the slot is empty.
+    seq: AtomicU64,
+    len: AtomicUsize,
+    /// The bytes of the record, packed in little-endian words.
+    data: Box<[AtomicU64]>,
+}
+
+impl Ring {
+    /// Create a ring of `capacity` records of at most `max_len` bytes each.
+    pub(crate) fn new(capacity: usize, max_len: usize) -> Self {
+        let slots = (0..capacity.max(1))
+            .map(|_| Slot {
+                version: AtomicU64::new(0),
+                seq: AtomicU64::new(0),
+                len: AtomicUsize::new(0),
+                data: (0..max_len.div_ceil(8))
+                    .map(|_| AtomicU64::new(0))
+                    .collect(),
+            })
+            .collect();
+        Self {
+            slots,
+            head: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Write the record, truncated to the maximum length, in place of the oldest one.
+    pub(crate) fn push(&self, seq: u64, text: &str) {
+        let index = self.head.fetch_add(1, Ordering::Relaxed) % self.slots.len();
+        let slot = &self.slots[index];
+        let version = slot.version.load(Ordering::Relaxed);
+        if version % 2 == 1
+            || slot
+                .version
+                .compare_exchange(version, version + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        // keep the data stores after the odd version, so that a reader can't see them with the previous version
+        fence(Ordering::Release);
+
+        let text = truncate(text, slot.data.len() * 8);
+        for (word, chunk) in slot.data.iter().zip(text.as_bytes().chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            word.store(u64::from_le_bytes(bytes), Ordering::Relaxed);
+        }
+        slot.len.store(text.len(), Ordering::Relaxed);
+        slot.seq.store(seq, Ordering::Relaxed);
+        slot.version.store(version + 2, Ordering::Release);
+    }
+
+    /// Read the records with their sequence numbers, skipping the slots that are being written.
+    pub(crate) fn read(&self) -> Vec<(u64, String)> {
+        self.slots.iter().filter_map(Slot::read).collect()
+    }
+
+    /// The number of records dropped because their slot was being written by another thread.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Slot {
+    fn read(&self) -> Option<(u64, String)> {
+        // retry a few times if a writer interferes, then give up on the slot
+        for _ in 0..3 {
+            let version = self.version.load(Ordering::Acquire);
+            if version % 2 == 1 {
+                continue;
+            }
+            let seq = self.seq.load(Ordering::Relaxed);
+            let len = self.len.load(Ordering::Relaxed).min(self.data.len() * 8);
+            let mut bytes: Vec<u8> = self
+                .data
+                .iter()
+                .flat_map(|word| word.load(Ordering::Relaxed).to_le_bytes())
+                .collect();
+            fence(Ordering::Acquire);
+            if self.version.load(Ordering::Relaxed) != version {
+                continue;
+            }
+            if seq == 0 {
+                return None;
+            }
+            bytes.truncate(len);
+            return Some((seq, String::from_utf8_lossy(&bytes).into_owned()));
+        }
+        None
+    }
+}
+
+/// Truncate the text to at most `max_len` bytes, at a character boundary.
+fn truncate(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
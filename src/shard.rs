@@ -0,0 +1,118 @@
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::buffer::{Buffer, Limit};
+use crate::lock::LockExt;
+
+static NEXT_SHARDS_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// The shards of the current thread, by the id of the `Shards` they belong to.
+    static THREAD_SHARDS: RefCell<Vec<(u64, Arc<Mutex<Shard>>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Per-thread buffers for the collected text, so that threads writing concurrently don't contend on a single lock.
+///
+/// Each write is numbered from a sequence shared by all threads, and the shards are merged into the collector's
+/// buffer in that order when the text is read. For a bounded collector, the shards are also merged when one of
+/// them exceeds the limit, so that they stay bounded as well.
+pub(crate) struct Shards {
+    id: u64,
+    next_seq: AtomicU64,
+    /// The shards of the threads that wrote and didn't exit since the last merge. Only locked when a thread writes
+    /// for the first time and when merging.
+    shards: Mutex<Vec<Arc<Mutex<Shard>>>>,
+    limit: Limit,
+}
+
+#[derive(Default)]
+struct Shard {
+    text: Vec<u8>,
+    /// The sequence number, end position in `text` and whether it is an event, of each write.
+    records: Vec<(u64, usize, bool)>,
+    /// The number of event records in `records`.
+    events: usize,
+}
+
+impl Shards {
+    pub(crate) fn new(limit: Limit) -> Self {
+        Self {
+            id: NEXT_SHARDS_ID.fetch_add(1, Ordering::Relaxed),
+            next_seq: AtomicU64::new(0),
+            shards: Mutex::new(vec![]),
+            limit,
+        }
+    }
+
+    /// Write to the current thread's shard. Returns `true` if the shards should be merged to stay within the limit.
+    pub(crate) fn write(&self, buf: &[u8], is_event: bool) -> bool {
+        let shard = THREAD_SHARDS.with(|thread_shards| {
+            let mut thread_shards = thread_shards.borrow_mut();
+            if let Some((_, shard)) = thread_shards.iter().find(|(id, _)| *id == self.id) {
+                return shard.clone();
+            }
+            // forget the shards of dropped collectors
+            thread_shards.retain(|(_, shard)| Arc::strong_count(shard) > 1);
+            let shard = Arc::new(Mutex::new(Shard::default()));
+            self.shards.lock_or_recover().push(shard.clone());
+            thread_shards.push((self.id, shard.clone()));
+            shard
+        });
+
+        let mut shard = shard.lock_or_recover();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        shard.text.extend_from_slice(buf);
+        let end = shard.text.len();
+        shard.records.push((seq, end, is_event));
+        shard.events += usize::from(is_event);
+        self.limit
+            .is_exceeded(shard.text.len(), shard.records.len(), shard.events)
+    }
+
+    /// Move the text of all shards to the buffer, ordered by sequence number.
+    ///
+    /// All the shards are locked before any is drained. As a write takes its sequence number while holding its
+    /// shard's lock, every write with a lower sequence number than a drained one is drained as well, so that a
+    /// write racing with the merge is never merged after a write with a greater sequence number.
+    ///
+    /// The shards of the threads that exited are removed once drained, so that threads that come and go, e.g. in a
+    /// pool of worker threads, don't make each merge lock more shards.
+    pub(crate) fn merge_into(&self, buffer: &mut Buffer<u8>) {
+        let mut shards = self.shards.lock_or_recover();
+        let mut locked = shards
+            .iter()
+            .map(|shard| shard.lock_or_recover())
+            .collect::<Vec<_>>();
+        let drained = locked
+            .iter_mut()
+            .map(|shard| std::mem::take(&mut **shard))
+            .collect::<Vec<_>>();
+        drop(locked);
+        // only the exited threads' shards are no longer referenced by their thread-locals
+        shards.retain(|shard| Arc::strong_count(shard) > 1);
+        drop(shards);
+
+        let mut records = drained
+            .iter()
+            .flat_map(|shard| {
+                let starts = std::iter::once(0).chain(shard.records.iter().map(|(_, end, _)| *end));
+                shard
+                    .records
+                    .iter()
+                    .zip(starts)
+                    .map(|((seq, end, is_event), start)| {
+                        (*seq, &shard.text[start..*end], *is_event)
+                    })
+            })
+            .collect::<Vec<_>>();
+        records.sort_unstable_by_key(|(seq, _, _)| *seq);
+        for (_, text, is_event) in records {
+            buffer.extend_from_slice(text, is_event);
+        }
+    }
+}
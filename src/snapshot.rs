@@ -0,0 +1,69 @@
+use std::fmt;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{
+    fmt::{format::Writer, FmtContext, FormatEvent, FormatFields},
+    registry::LookupSpan,
+};
+
+use crate::event::{FieldValue, FieldVisitor};
+use crate::layer::span_fields;
+
+/// The one-line format designed for snapshots, `LEVEL target: span{field=value}:child: message field=value`,
+/// with the fields sorted by name and without source locations, so that diffs stay minimal.
+pub(crate) struct SnapshotFormat;
+
+impl<S, N> FormatEvent<S, N> for SnapshotFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        write!(writer, "{} {}:", metadata.level(), metadata.target())?;
+
+        for span in ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+        {
+            write!(writer, " {}", span.name())?;
+            let fields = span_fields(&span);
+            if !fields.is_empty() {
+                write!(writer, "{{")?;
+                write_fields(&mut writer, fields, false)?;
+                write!(writer, "}}")?;
+            }
+            write!(writer, ":")?;
+        }
+
+        let mut fields = vec![];
+        let mut message = None;
+        event.record(&mut FieldVisitor::with_message(&mut fields, &mut message));
+        if let Some(message) = message {
+            write!(writer, " {message}")?;
+        }
+        write_fields(&mut writer, fields, true)?;
+        writeln!(writer)
+    }
+}
+
+/// Write the fields sorted by name and separated by spaces, with a leading space if `leading_space` is set.
+fn write_fields(
+    writer: &mut Writer<'_>,
+    mut fields: Vec<(String, FieldValue)>,
+    leading_space: bool,
+) -> fmt::Result {
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 || leading_space {
+            write!(writer, " ")?;
+        }
+        write!(writer, "{name}={value}")?;
+    }
+    Ok(())
+}
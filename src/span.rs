@@ -0,0 +1,139 @@
+use std::fmt;
+use tracing::Level;
+
+use crate::event::FieldValue;
+
+/// What happened to a span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanEventKind {
+    /// The span was created.
+    New,
+    /// The span was entered.
+    Enter,
+    /// The span was exited.
+    Exit,
+    /// The span was closed, i.e. all handles to it were dropped.
+    Close,
+    /// Values were recorded on the span with `Span::record`.
+    Record,
+}
+
+/// A lifecycle event of a span, as captured by a [`TracingCollector`](crate::TracingCollector).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanEvent {
+    pub kind: SpanEventKind,
+    /// An identifier for the span that is unique within the process, unlike `tracing`'s span ids which are reused.
+    pub id: u64,
+    /// The identifier of the span's parent, if any.
+    pub parent: Option<u64>,
+    pub name: &'static str,
+    pub target: &'static str,
+    pub level: Level,
+    /// The initial fields for [`SpanEventKind::New`], the recorded values for [`SpanEventKind::Record`] and
+    /// empty otherwise.
+    pub fields: Vec<(String, FieldValue)>,
+}
+
+impl fmt::Display for SpanEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            SpanEventKind::New => "new",
+            SpanEventKind::Enter => "enter",
+            SpanEventKind::Exit => "exit",
+            SpanEventKind::Close => "close",
+            SpanEventKind::Record => "record",
+        };
+        write!(f, "{kind} {}", self.name)?;
+        write_fields(f, &self.fields)
+    }
+}
+
+/// An indented tree of spans built from their lifecycle events, where each span is shown with its fields
+/// (including values recorded later) below its parent.
+///
+/// Created with [`TracingCollector::span_tree`](crate::TracingCollector::span_tree) and meant to be
+/// snapshot-tested:
+///
+/// ```text
+/// request{id=42}
+///   load{path="a.txt" size=12}
+///   store
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanTree {
+    nodes: Vec<SpanNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SpanNode {
+    id: u64,
+    parent: Option<u64>,
+    name: &'static str,
+    fields: Vec<(String, FieldValue)>,
+}
+
+impl SpanTree {
+    pub(crate) fn new(events: &[SpanEvent]) -> Self {
+        let mut nodes: Vec<SpanNode> = vec![];
+        for event in events {
+            match event.kind {
+                SpanEventKind::New => nodes.push(SpanNode {
+                    id: event.id,
+                    parent: event.parent,
+                    name: event.name,
+                    fields: event.fields.clone(),
+                }),
+                SpanEventKind::Record => {
+                    if let Some(node) = nodes.iter_mut().find(|node| node.id == event.id) {
+                        for (name, value) in &event.fields {
+                            match node.fields.iter_mut().find(|(field, _)| field == name) {
+                                Some((_, existing)) => *existing = value.clone(),
+                                None => node.fields.push((name.clone(), value.clone())),
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self { nodes }
+    }
+
+    fn write_node(&self, f: &mut fmt::Formatter<'_>, node: &SpanNode, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", node.name, indent = depth * 2)?;
+        write_fields(f, &node.fields)?;
+        writeln!(f)?;
+        for child in self.nodes.iter().filter(|n| n.parent == Some(node.id)) {
+            self.write_node(f, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SpanTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // spans whose parent isn't part of the tree (e.g. because it was cleared) are shown as roots
+        let roots = self.nodes.iter().filter(|node| {
+            node.parent
+                .is_none_or(|parent| !self.nodes.iter().any(|n| n.id == parent))
+        });
+        for root in roots {
+            self.write_node(f, root, 0)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[(String, FieldValue)]) -> fmt::Result {
+    if fields.is_empty() {
+        return Ok(());
+    }
+    write!(f, "{{")?;
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{name}={value}")?;
+    }
+    write!(f, "}}")
+}
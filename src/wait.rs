@@ -0,0 +1,160 @@
+use futures_core::Stream;
+use std::{
+    future::{self, Future},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::buffer::Buffer;
+use crate::event::CollectedEvent;
+use crate::lock::LockExt;
+use crate::matcher::EventMatcher;
+use crate::TracingCollector;
+
+/// Notifies the threads and tasks waiting for collected events. The condvar is used with the events' mutex.
+#[derive(Default)]
+pub(crate) struct Notify {
+    condvar: Condvar,
+    wakers: Mutex<Vec<Waker>>,
+    /// The number of `wait_for` calls and event streams, so that nothing is done for each event when there are none.
+    waiters: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl Notify {
+    /// Wake everything waiting, after an event was pushed (and the events' mutex released).
+    ///
+    /// The waiters are counted while holding the events' mutex, before they check the events, so a waiter that
+    /// missed the event is counted once the mutex is released.
+    pub(crate) fn notify(&self) {
+        if self.waiters.load(Ordering::Relaxed) > 0 {
+            self.wake_all();
+        }
+    }
+
+    /// Wake everything waiting for the last time, when the collector is dropped.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.wake_all();
+    }
+
+    fn wake_all(&self) {
+        self.condvar.notify_all();
+        let wakers = std::mem::take(&mut *self.wakers.lock_or_recover());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Register the waker, while holding the events' mutex so that no notification is missed.
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock_or_recover();
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    /// Count a waiter until the returned guard is dropped.
+    fn waiter(self: &Arc<Self>) -> Waiter {
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        Waiter(self.clone())
+    }
+}
+
+/// Counts a waiter of a `Notify` while it exists.
+struct Waiter(Arc<Notify>);
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.0.waiters.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Waiting for events emitted by other threads or tasks.
+impl<M> TracingCollector<M> {
+    /// Block the current thread until an event matches or the timeout expires, and return the first matching
+    /// event, if any. The events that are already collected (and not consumed) are checked first.
+    ///
+    /// This is useful to wait for background workers. In async code, use `next_event` or `event_stream` instead.
+    pub fn wait_for(
+        &self,
+        matcher: impl Into<EventMatcher>,
+        timeout: Duration,
+    ) -> Option<CollectedEvent> {
+        let matcher = matcher.into();
+        let deadline = Instant::now() + timeout;
+        let mut events = self.events.lock_or_recover();
+        let _waiter = self.notify.waiter();
+        let mut position = 0;
+        loop {
+            if let Some(event) = events.since(position).iter().find(|e| matcher.matches(e)) {
+                return Some(event.clone());
+            }
+            position = events.end();
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            events = self
+                .notify
+                .condvar
+                .wait_timeout(events, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Wait for the next event collected after this call.
+    pub fn next_event(&self) -> impl Future<Output = CollectedEvent> + '_ {
+        let mut stream = self.event_stream();
+        future::poll_fn(move |cx| match Pin::new(&mut stream).poll_next(cx) {
+            Poll::Ready(event) => Poll::Ready(event.expect("the collector is borrowed")),
+            Poll::Pending => Poll::Pending,
+        })
+    }
+
+    /// Create a `Stream` of the events collected after this call, which ends when the collector is dropped.
+    pub fn event_stream(&self) -> EventStream {
+        let events = self.events.lock_or_recover();
+        EventStream {
+            position: events.end(),
+            events: self.events.clone(),
+            waiter: self.notify.waiter(),
+        }
+    }
+}
+
+/// A `Stream` of the events collected by a [`TracingCollector`]. Created with
+/// [`TracingCollector::event_stream`].
+///
+/// The stream yields copies of the events, so reading the collector doesn't affect it (unless the events are
+/// consumed before the stream gets to them).
+pub struct EventStream {
+    events: Arc<Mutex<Buffer<CollectedEvent>>>,
+    waiter: Waiter,
+    position: usize,
+}
+
+impl Stream for EventStream {
+    type Item = CollectedEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<CollectedEvent>> {
+        let events = self.events.lock_or_recover();
+        let remaining = events.since(self.position);
+        if let Some(event) = remaining.first().cloned() {
+            let position = events.end() - remaining.len() + 1;
+            drop(events);
+            self.position = position;
+            return Poll::Ready(Some(event));
+        }
+        let notify = &self.waiter.0;
+        notify.register(cx.waker());
+        // Checked after registering, as `close` sets the flag before waking the registered wakers.
+        if notify.closed.load(Ordering::Acquire) {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
@@ -0,0 +1,75 @@
+use std::panic::{self, AssertUnwindSafe};
+use tracing::Level;
+use tracing_collector::{EventMatcher, TracingCollector};
+
+#[test]
+fn test_assertions() {
+    let log = TracingCollector::init_debug_level();
+    tracing::info!(user = "bob", "logged in");
+    tracing::warn!(attempt = 1, "retrying upload");
+    tracing::warn!(attempt = 2, "retrying upload");
+    tracing::info!("upload done");
+
+    log.assert_contains(EventMatcher::new().level(Level::WARN).field("attempt", 2));
+    log.assert_contains(EventMatcher::new().field("user", "bob"));
+    log.assert_not_logged(Level::ERROR);
+    log.assert_count(EventMatcher::new().message_contains("retrying"), 2);
+    log.assert_sequence([
+        EventMatcher::new().message_contains("logged in"),
+        EventMatcher::new().field("attempt", 2u8),
+        EventMatcher::new().message_contains("done"),
+    ]);
+
+    // assertions don't consume the events
+    assert_eq!(log.events().len(), 4);
+}
+
+#[test]
+fn test_assertion_failure() {
+    let log = TracingCollector::init_debug_level();
+    tracing::info!(user = "bob", "logged in");
+    tracing::warn!(attempt = 2, "retrying upload");
+
+    let message = panic_message(|| {
+        log.assert_contains(
+            EventMatcher::new()
+                .level(Level::WARN)
+                .message_contains("retry")
+                .field("attempt", 3),
+        )
+    });
+    insta::assert_snapshot!(message, @r###"
+    expected an event matching `level == WARN, message contains "retry", attempt == 3`
+    closest event:
+      WARN assertions: retrying upload attempt=2
+      - attempt == 3
+      + attempt == 2
+    collected events (2):
+      INFO assertions: logged in user="bob"
+      WARN assertions: retrying upload attempt=2
+    "###);
+
+    let message = panic_message(|| {
+        log.assert_sequence([
+            EventMatcher::new().field("user", "bob"),
+            EventMatcher::new().level(Level::ERROR),
+        ])
+    });
+    insta::assert_snapshot!(message, @r###"
+    expected event #1 of the sequence to match `level == ERROR` after the previous ones
+    closest event:
+      WARN assertions: retrying upload attempt=2
+      - level == ERROR
+      + level == WARN
+    events after the previous ones (1):
+      WARN assertions: retrying upload attempt=2
+    "###);
+}
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).expect_err("expected a panic");
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .expect("expected a formatted panic message")
+}
@@ -0,0 +1,101 @@
+use tracing_collector::{FmtSpan, TracingCollector};
+
+#[test]
+fn test_max_events() {
+    let log = TracingCollector::builder()
+        .snapshot()
+        .with_max_events(3)
+        .init();
+    let cp = log.checkpoint();
+    for i in 0..10 {
+        tracing::info!(i, "Log");
+    }
+
+    assert_eq!(log.dropped_events(), 7);
+    assert_eq!(log.events().len(), 3);
+    insta::assert_snapshot!(log.since(cp), @r###"
+    ㏒INFO bounded: Log i=7
+    INFO bounded: Log i=8
+    INFO bounded: Log i=9
+    "###);
+
+    // reading doesn't count as dropping
+    let _ = log.take();
+    let _ = log.take_events();
+    tracing::info!("Log");
+    assert_eq!(log.dropped_events(), 7);
+    assert_eq!(log.events().len(), 1);
+}
+
+#[test]
+fn test_max_bytes() {
+    let log = TracingCollector::builder()
+        .snapshot()
+        .with_max_bytes(64)
+        .init();
+    for i in 0..100 {
+        tracing::info!(i, "Log");
+    }
+
+    // whole events are evicted, so that the text starts with a complete line
+    let text = log.render().prefix(None).to_string();
+    assert!(text.len() <= 64, "{text}");
+    assert!(text.starts_with("INFO bounded: Log i="), "{text}");
+    assert!(text.ends_with("INFO bounded: Log i=99\n"), "{text}");
+    assert!(log.dropped_bytes() > 0);
+    // the events are only evicted from the text
+    assert_eq!(log.dropped_events(), 100 - text.lines().count());
+    assert_eq!(log.events().len(), 100);
+}
+
+#[test]
+fn test_max_events_with_span_events() {
+    let log = TracingCollector::builder()
+        .snapshot()
+        .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+        .with_max_events(2)
+        .init();
+    tracing::info_span!("s").in_scope(|| {
+        tracing::info!("a");
+        tracing::info!("b");
+    });
+
+    // the span lines don't count as events
+    assert_eq!(log.dropped_events(), 0);
+    insta::assert_snapshot!(log.peek(), @r###"
+    ㏒INFO bounded: s: new
+    INFO bounded: s: a
+    INFO bounded: s: b
+    INFO bounded: s: close
+    "###);
+
+    // they are evicted with the event that follows them
+    tracing::info!("c");
+    assert_eq!(log.dropped_events(), 1);
+    insta::assert_snapshot!(log, @r###"
+    ㏒INFO bounded: s: b
+    INFO bounded: s: close
+    INFO bounded: c
+    "###);
+}
+
+#[test]
+fn test_max_events_with_only_span_events() {
+    for sharded in [false, true] {
+        let log = TracingCollector::builder()
+            .snapshot()
+            .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+            .with_max_events(10)
+            .with_sharded_writer(sharded)
+            .init();
+        for i in 0..10_000 {
+            tracing::info_span!("s", i).in_scope(|| {});
+        }
+
+        // beyond `max_events` of them, the span lines count toward the limit
+        let text = log.render().prefix(None).to_string();
+        assert_eq!(text.lines().count(), 20, "{text}");
+        assert!(text.ends_with("INFO bounded: s{i=9999}: close\n"), "{text}");
+        assert!(log.dropped_bytes() > 0);
+    }
+}
@@ -0,0 +1,34 @@
+use tracing::Level;
+use tracing_collector::{FmtSpan, TracingCollector};
+
+#[test]
+fn test_compact_with_target() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_target(true)
+        .with_file(false)
+        .with_line_number(false)
+        .with_max_level(Level::INFO)
+        .init();
+    tracing::info!(answer = 42, "First log");
+    tracing::debug!("Filtered out");
+
+    insta::assert_snapshot!(log, @"㏒ INFO builder: First log answer=42");
+}
+
+#[test]
+fn test_span_events() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+        .init();
+    tracing::info_span!("request", id = 1).in_scope(|| tracing::info!("Handled"));
+
+    insta::assert_snapshot!(log, @r###"
+    ㏒ INFO request: new id=1
+     INFO request: Handled id=1
+     INFO request: close id=1
+    "###);
+}
@@ -0,0 +1,48 @@
+use std::{env, fs, process::Command};
+use tracing_collector::{Echo, TracingCollector};
+
+#[test]
+fn test_echo_to_file() {
+    let path = env::temp_dir().join(format!("tracing-collector-echo-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let log = TracingCollector::builder()
+        .with_echo(Echo::File(path.clone()))
+        .init();
+    tracing::info!("First log");
+
+    let echoed = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    // the echo keeps the colors, the collected traces don't
+    assert!(echoed.contains("\u{1b}["));
+    assert!(echoed.contains("First log"));
+    assert!(!log.to_string().contains("\u{1b}["));
+}
+
+#[test]
+#[ignore = "panics on purpose, run by test_echo_on_failure"]
+fn failing_test_with_echo_on_failure() {
+    let log = TracingCollector::builder()
+        .with_echo(Echo::OnFailure)
+        .init();
+    tracing::info!("Read by the test");
+    let _ = log.to_string();
+    tracing::info!("Not read by the test");
+    panic!("test failure");
+}
+
+#[test]
+fn test_echo_on_failure() {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--ignored", "--exact", "failing_test_with_echo_on_failure"])
+        .args(["--nocapture", "--test-threads=1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stdout.contains("Read by the test"));
+    assert!(stderr.contains("---- collected traces ----"));
+    assert!(stderr.contains("Read by the test"));
+    assert!(stderr.contains("Not read by the test"));
+}
@@ -0,0 +1,31 @@
+use std::panic::{self, AssertUnwindSafe};
+use tracing_collector::{EventMatcher, TracingCollector};
+
+#[test]
+fn test_try_read() {
+    let log = TracingCollector::builder().snapshot().init();
+    tracing::info!("First log");
+
+    insta::assert_snapshot!(log.try_read().unwrap(), @"㏒INFO errors: First log");
+    insta::assert_snapshot!(log.try_read().unwrap(), @"㏒");
+}
+
+#[test]
+fn test_recover_from_panicking_matcher() {
+    let log = TracingCollector::builder().snapshot().init();
+    tracing::info!(answer = 42, "First log");
+
+    // the predicate panics while the events are locked, poisoning the lock
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        log.events_matching(EventMatcher::new().field_matches("answer", |_| panic!("oops")))
+    }));
+    assert!(result.is_err());
+
+    tracing::info!("Second log");
+    assert_eq!(log.events().len(), 2);
+    log.assert_contains(EventMatcher::new().message_contains("Second"));
+    insta::assert_snapshot!(log.try_read().unwrap(), @r###"
+    ㏒INFO errors: First log answer=42
+    INFO errors: Second log
+    "###);
+}
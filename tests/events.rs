@@ -0,0 +1,37 @@
+use tracing::Level;
+use tracing_collector::{FieldValue, TracingCollector};
+
+#[test]
+fn test_events() {
+    let log = TracingCollector::init_debug_level();
+    let span = tracing::info_span!("request", id = 42);
+    let _enter = span.enter();
+    tracing::warn!(attempt = 3, retry = true, "retrying {}", "upload");
+
+    let events = log.events();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.level, Level::WARN);
+    assert_eq!(event.target, "events");
+    assert_eq!(event.message.as_deref(), Some("retrying upload"));
+    assert_eq!(event.field("attempt"), Some(&FieldValue::I64(3)));
+    assert_eq!(event.field("retry"), Some(&FieldValue::Bool(true)));
+    assert_eq!(event.file, Some("tests/events.rs"));
+    assert_eq!(event.line, Some(9));
+    assert_eq!(event.spans.len(), 1);
+    assert_eq!(event.spans[0].name, "request");
+    assert_eq!(event.spans[0].field("id"), Some(&FieldValue::I64(42)));
+}
+
+#[test]
+fn test_events_are_not_consumed_by_display() {
+    let log = TracingCollector::init_info_level();
+    tracing::info!("First log");
+    tracing::debug!("Filtered out");
+
+    let _ = log.to_string();
+    assert_eq!(log.events().len(), 1);
+
+    log.clear();
+    assert!(log.events().is_empty());
+}
@@ -0,0 +1,28 @@
+use tracing_collector::TracingCollector;
+
+#[test]
+fn test_env_filter() {
+    let log = TracingCollector::init_with_env_filter("filter=debug,hyper=warn");
+    tracing::debug!("Collected");
+    tracing::trace!("Filtered out");
+    tracing::info!(target: "hyper", "Filtered out");
+    tracing::warn!(target: "hyper", "Collected");
+
+    let messages = log
+        .events()
+        .into_iter()
+        .map(|event| format!("{} {}", event.target, event.message.unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(messages, ["filter Collected", "hyper Collected"]);
+}
+
+#[test]
+fn test_filter_fn() {
+    let log = TracingCollector::builder()
+        .with_filter_fn(|metadata| metadata.target() != "sqlx")
+        .init();
+    tracing::info!(target: "sqlx", "Filtered out");
+    tracing::info!("Collected");
+
+    assert_eq!(log.events().len(), 1);
+}
@@ -0,0 +1,21 @@
+use std::panic;
+use tracing_collector::TracingCollector;
+
+#[test]
+fn test_global_collector_with_existing_subscriber() {
+    tracing::subscriber::set_global_default(tracing_subscriber::registry()).unwrap();
+
+    // every attempt fails with the same message, rather than a poisoned `Once` after the first one
+    for _ in 0..2 {
+        let Err(panic) = panic::catch_unwind(|| TracingCollector::builder().global().init()) else {
+            panic!("the global collector was installed");
+        };
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.starts_with(
+                "failed to install the global collector, another global subscriber is already set"
+            ),
+            "{message}"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use tracing_collector::TracingCollector;
+
+async fn work(id: usize) {
+    for step in 0..5 {
+        tracing::info!(id, step, "Working");
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_instrument_multi_thread() {
+    for log in [
+        TracingCollector::init_info_level(),
+        TracingCollector::builder().global().init(),
+    ] {
+        let tasks = (0..4)
+            .map(|id| tokio::spawn(log.instrument(work(id))))
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        log.instrument(work(4)).await;
+
+        assert_eq!(log.events().len(), 25);
+    }
+}
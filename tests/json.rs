@@ -0,0 +1,57 @@
+use tracing_collector::TracingCollector;
+
+#[test]
+fn test_json_events() {
+    let log = TracingCollector::init_debug_level();
+    tracing::info_span!("request", id = 1).in_scope(|| {
+        tracing::info!(answer = 42, name = "bob", "Handled");
+    });
+
+    insta::assert_json_snapshot!(log.json_events(), { "[].line" => "[line]" }, @r#"
+    [
+      {
+        "fields": {
+          "answer": 42,
+          "name": "bob"
+        },
+        "file": "tests/json.rs",
+        "level": "INFO",
+        "line": "[line]",
+        "message": "Handled",
+        "spans": [
+          {
+            "fields": {
+              "id": 1
+            },
+            "name": "request",
+            "target": "json"
+          }
+        ],
+        "target": "json"
+      }
+    ]
+    "#);
+}
+
+#[test]
+fn test_json_format() {
+    let log = TracingCollector::builder()
+        .json()
+        .with_file(false)
+        .with_line_number(false)
+        .init();
+    tracing::info!(answer = 42, "Handled");
+
+    let lines = log.to_string();
+    let value: serde_json::Value =
+        serde_json::from_str(lines.trim_start_matches('㏒').trim()).unwrap();
+    insta::assert_json_snapshot!(value, @r#"
+    {
+      "fields": {
+        "answer": 42,
+        "message": "Handled"
+      },
+      "level": "INFO"
+    }
+    "#);
+}
@@ -0,0 +1,52 @@
+use tracing::{subscriber::with_default, Level};
+use tracing_collector::{EventMatcher, TracingCollector};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn test_layer_in_existing_subscriber() {
+    let (layer, log) = TracingCollector::layer();
+    // the application's own layers
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::sink))
+        .with(layer);
+
+    with_default(subscriber, || {
+        tracing::info_span!("request", id = 1).in_scope(|| tracing::info!(answer = 42, "Handled"));
+    });
+    tracing::info!("Not collected");
+
+    assert_eq!(log.events().len(), 1);
+    log.assert_contains(
+        EventMatcher::new()
+            .message_contains("Handled")
+            .field("answer", 42)
+            .parent_span("request"),
+    );
+    insta::assert_snapshot!(log.span_tree(), @"request{id=1}");
+}
+
+#[test]
+fn test_layers_with_own_filters() {
+    let (info_layer, info_log) = TracingCollector::builder()
+        .snapshot()
+        .with_max_level(Level::INFO)
+        .build_layer();
+    let (debug_layer, debug_log) = TracingCollector::builder()
+        .snapshot()
+        .with_max_level(Level::DEBUG)
+        .build_layer();
+    let subscriber = tracing_subscriber::registry()
+        .with(info_layer)
+        .with(debug_layer);
+
+    with_default(subscriber, || {
+        tracing::info!("First log");
+        tracing::debug!("Second log");
+    });
+
+    insta::assert_snapshot!(info_log, @"㏒INFO layer: First log");
+    insta::assert_snapshot!(debug_log, @r###"
+    ㏒INFO layer: First log
+    DEBUG layer: Second log
+    "###);
+}
@@ -0,0 +1,5 @@
+#[test]
+fn test_layer_collector_has_no_bind() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/layer/*.rs");
+}
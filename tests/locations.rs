@@ -0,0 +1,60 @@
+use tracing_collector::{Locations, TracingCollector};
+
+fn log_twice() {
+    tracing::info!("First log");
+    tracing::info!("Second log");
+}
+
+#[test]
+fn test_locations() {
+    let log = TracingCollector::builder()
+        .with_locations(Locations::Ordinal)
+        .init();
+    log_twice();
+    log_twice();
+
+    insta::assert_snapshot!(log, @r###"
+    ㏒   INFO  First log
+        at tests/locations.rs:#1
+
+       INFO  Second log
+        at tests/locations.rs:#2
+
+       INFO  First log
+        at tests/locations.rs:#1
+
+       INFO  Second log
+        at tests/locations.rs:#2
+    "###);
+
+    for (locations, expected) in [
+        (Locations::HideLineNumbers, "tests/locations.rs:"),
+        (Locations::FileName, "locations.rs:"),
+    ] {
+        let log = TracingCollector::builder()
+            .compact()
+            .with_locations(locations)
+            .init();
+        tracing::info!("Failed to parse src/config/app.rs: bad");
+        assert_eq!(
+            log.to_string(),
+            format!("㏒ INFO {expected} Failed to parse src/config/app.rs: bad\n")
+        );
+    }
+}
+
+#[test]
+fn test_normalized_paths() {
+    let log = TracingCollector::builder()
+        .json()
+        .with_locations(Locations::Ordinal)
+        .with_normalized_paths(true)
+        .init();
+    tracing::info!(
+        file = "src/config/app.rs:12",
+        "Failed to parse src/config/app.rs"
+    );
+
+    // only the location is rewritten, not the paths in the message and fields
+    insta::assert_snapshot!(log, @r###"㏒{"level":"INFO","fields":{"message":"Failed to parse src/config/app.rs","file":"src/config/app.rs:12"},"filename":"tests/locations.rs","line_number":"#1"}"###);
+}
@@ -0,0 +1,49 @@
+use tracing_collector::TracingCollector;
+
+#[tracing_collector::test(level = "info")]
+fn test_binding() {
+    tracing::info!("Collected");
+    tracing::debug!("Filtered out");
+
+    assert_eq!(log.events().len(), 1);
+}
+
+#[tracing_collector::test(filter = "macros=debug")]
+fn test_parameter(mut collector: TracingCollector) {
+    collector.remove_prefix();
+    tracing::debug!("Collected");
+
+    assert_eq!(collector.events().len(), 1);
+}
+
+#[tracing_collector::test]
+fn test_result() -> Result<(), std::num::ParseIntError> {
+    let answer: u32 = "42".parse()?;
+    tracing::info!(answer);
+
+    assert_eq!(log.events().len(), 1);
+    Ok(())
+}
+
+#[tracing_collector::test(tokio, flavor = "multi_thread", worker_threads = 2)]
+async fn test_tokio() {
+    tokio::spawn(log.instrument(async { tracing::info!("Collected") }))
+        .await
+        .unwrap();
+
+    assert_eq!(log.events().len(), 1);
+}
+
+#[tracing_collector::test(async_std)]
+async fn test_async_std() {
+    async_std::task::spawn(log.instrument(async { tracing::info!("Collected") })).await;
+
+    assert_eq!(log.events().len(), 1);
+}
+
+#[tracing_collector::test(level = "info", snapshot)]
+fn test_snapshot() {
+    tracing::info!("Read by the test");
+    let _ = log.to_string();
+    tracing::info!("Snapshot at the end of the test");
+}
@@ -0,0 +1,45 @@
+use tracing::Level;
+use tracing_collector::{EventMatcher, FieldValue, TracingCollector};
+
+mod db {
+    pub fn query() {
+        tracing::error!(target: "app::db::pool", table = "users", "connection lost");
+    }
+}
+
+fn database_error() -> EventMatcher {
+    EventMatcher::new().level(Level::ERROR).target("app::db*")
+}
+
+#[test]
+fn test_matcher() {
+    let log = TracingCollector::init_trace_level();
+    tracing::trace!(target: "app::http", "request");
+    tracing::info_span!("handler", user = "bob").in_scope(|| {
+        tracing::info_span!("query").in_scope(db::query);
+        tracing::warn!(elapsed_ms = 1500u64, "slow request");
+    });
+
+    log.assert_count(database_error(), 1);
+    log.assert_contains(database_error().parent_span("query").in_span("handler"));
+    log.assert_contains(EventMatcher::new().message_matches(r"^slow \w+$"));
+    log.assert_contains(EventMatcher::new().field_matches(
+        "elapsed_ms",
+        |value| matches!(value, FieldValue::U64(ms) if *ms > 1000),
+    ));
+    log.assert_count(EventMatcher::new().has_field("table"), 1);
+    log.assert_count(EventMatcher::new().target("app::*"), 2);
+    log.assert_count(EventMatcher::not(Level::TRACE), 2);
+
+    let warnings_or_db = EventMatcher::new().level(Level::WARN).or(database_error());
+    let messages: Vec<_> = log
+        .events_matching(&warnings_or_db)
+        .into_iter()
+        .filter_map(|event| event.message)
+        .collect();
+    assert_eq!(messages, ["connection lost", "slow request"]);
+    assert_eq!(
+        warnings_or_db.to_string(),
+        "(level == WARN) or (level == ERROR, target matches app::db*)"
+    );
+}
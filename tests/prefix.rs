@@ -0,0 +1,44 @@
+use tracing_collector::{Locations, Prefix, TracingCollector};
+
+#[test]
+fn test_string_prefixes() {
+    let mut log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .init();
+    log.set_prefix("> ");
+    tracing::info!("First log");
+    tracing::info!("Second log");
+    insta::assert_snapshot!(log, @r###"
+    >  INFO First log
+     INFO Second log
+    "###);
+
+    log.set_prefix(Prefix::EachLine("| ".into()));
+    tracing::info!("First log");
+    tracing::info!("Second log");
+    insta::assert_snapshot!(log, @r###"
+    |  INFO First log
+    |  INFO Second log
+    "###);
+}
+
+#[test]
+fn test_dedent() {
+    let mut log = TracingCollector::builder()
+        .with_locations(Locations::FileName)
+        .init();
+    log.set_prefix(Prefix::Dedent);
+    tracing::info!("First log");
+    tracing::info_span!("request").in_scope(|| tracing::debug!("Second log"));
+
+    insta::assert_snapshot!(log, @r###"
+     INFO  First log
+      at prefix.rs
+
+    DEBUG  Second log
+      at prefix.rs
+      in request
+    "###);
+}
@@ -0,0 +1,46 @@
+use tracing_collector::TracingCollector;
+
+#[test]
+fn test_peek_and_take() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .init();
+    tracing::info!("First log");
+
+    assert_eq!(log.peek(), log.peek());
+    insta::assert_snapshot!(log.take(), @"㏒ INFO First log");
+    insta::assert_snapshot!(log.peek(), @"㏒");
+}
+
+#[test]
+fn test_checkpoint() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .init();
+    tracing::info!("First log");
+    let cp = log.checkpoint();
+    tracing::info!("Second log");
+
+    insta::assert_snapshot!(log.since(cp), @"㏒ INFO Second log");
+    assert_eq!(log.events_since(cp).len(), 1);
+
+    // consuming the traces doesn't invalidate the checkpoint
+    let _ = log.to_string();
+    tracing::info!("Third log");
+    insta::assert_snapshot!(log.since(cp), @"㏒ INFO Third log");
+}
+
+#[test]
+fn test_take_events() {
+    let log = TracingCollector::init_info_level();
+    tracing::info!("First log");
+
+    assert_eq!(log.take_events().len(), 1);
+    assert!(log.take_events().is_empty());
+    tracing::info!("Second log");
+    assert_eq!(log.events().len(), 1);
+}
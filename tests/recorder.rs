@@ -0,0 +1,72 @@
+use std::{fs, thread};
+use tracing::subscriber::with_default;
+use tracing_collector::FlightRecorder;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn test_global_ring() {
+    let recorder = FlightRecorder::builder()
+        .with_capacity(3)
+        .with_max_line_len(40)
+        .build();
+    with_default(
+        tracing_subscriber::registry().with(recorder.layer()),
+        || {
+            for i in 0..4 {
+                tracing::info!(i, "Log");
+            }
+            tracing::info_span!("request").in_scope(|| {
+                tracing::warn!(reason = "a long reason that gets truncated", "Slow");
+            });
+        },
+    );
+
+    insta::assert_snapshot!(recorder.dump(), @r###"
+    INFO recorder: Log i=2
+    INFO recorder: Log i=3
+    WARN recorder: request: Slow reason="a l
+    "###);
+    assert_eq!(recorder.dropped_events(), 0);
+}
+
+#[test]
+fn test_per_thread_rings_and_dump_on_error() {
+    let path = std::env::temp_dir().join(format!("flight-recorder-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let recorder = FlightRecorder::builder()
+        .with_capacity(2)
+        .per_thread()
+        .dump_on_error()
+        .dump_to_file(&path)
+        .build();
+
+    // the second thread, then this one, reuse the ring of the thread that exited before
+    for (name, count) in [("first", 3), ("second", 1)] {
+        let layer = recorder.layer();
+        thread::spawn(move || {
+            with_default(tracing_subscriber::registry().with(layer), || {
+                for i in 0..count {
+                    tracing::info!(thread = name, i, "Log");
+                }
+            })
+        })
+        .join()
+        .unwrap();
+    }
+    // a burst of errors is dumped once
+    with_default(
+        tracing_subscriber::registry().with(recorder.layer()),
+        || {
+            tracing::error!("Failed");
+            tracing::error!("Failed again");
+        },
+    );
+
+    let dump = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    insta::assert_snapshot!(dump, @r###"
+    ---- flight recorder (error) ----
+    INFO recorder: Log thread="second" i=0
+    ERROR recorder: Failed
+    "###);
+}
@@ -0,0 +1,55 @@
+use tracing_collector::{Redaction, TracingCollector};
+
+#[test]
+fn test_redaction_presets() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .with_redaction(Redaction::uuid())
+        .with_redaction(Redaction::iso8601())
+        .with_redaction(Redaction::hex_address())
+        .with_redaction(Redaction::tmp_path())
+        .with_redaction(Redaction::duration())
+        .init();
+    tracing::info!(id = %"936da01f-9abd-4d9d-80c7-02af85c822a8", "Created");
+    tracing::info!(at = "2024-01-31T12:00:00.123+01:00", "Scheduled");
+    tracing::info!(ptr = ?0x7ffd5e8c as *const u8, path = "/tmp/.tmpA1b2C3/data.db", "Opened");
+    tracing::info!("Done in 12.5ms, waited 3s");
+
+    insta::assert_snapshot!(log, @r###"
+    ㏒ INFO Created id=[uuid]
+     INFO Scheduled at="[timestamp]"
+     INFO Opened ptr=[address] path="[tmp]"
+     INFO Done in [duration], waited [duration]
+    "###);
+}
+
+#[test]
+fn test_field_and_regex_redactions() {
+    for builder in [
+        TracingCollector::builder().compact(),
+        TracingCollector::builder().json(),
+        TracingCollector::builder(),
+    ] {
+        let log = builder
+            .with_redaction(Redaction::field("request_id", "[id]"))
+            .init();
+        tracing::info!(request_id = "a1b2", user = "bob", "Request");
+        tracing::info!(request_id = 4711, "Request");
+        let log = log.to_string();
+        assert!(!log.contains("a1b2") && !log.contains("4711"), "{log}");
+        assert_eq!(log.matches("[id]").count(), 2, "{log}");
+    }
+
+    let mut log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .init();
+    log.add_redaction(Redaction::regex(r"127\.0\.0\.1:(\d+)", "[host]:[port]"));
+    log.add_redaction(Redaction::regex(r"user (?<name>\w+)", "user <$name>"));
+    tracing::info!("Listening on 127.0.0.1:51234 as user bob");
+
+    insta::assert_snapshot!(log, @"㏒ INFO Listening on [host]:[port] as user <bob>");
+}
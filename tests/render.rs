@@ -0,0 +1,23 @@
+use tracing_collector::{Prefix, TracingCollector};
+
+#[test]
+fn test_with_prefix_and_render() {
+    let log = TracingCollector::builder()
+        .compact()
+        .with_file(false)
+        .with_line_number(false)
+        .init()
+        .with_prefix("> ");
+    tracing::info!("First log");
+
+    assert_eq!(log.render().prefix(None).peek(), " INFO First log\n");
+    insta::assert_snapshot!(log.render().prefix(Some(Prefix::EachLine("| ".into()))), @"|  INFO First log");
+
+    tracing::info!("Second log");
+    insta::assert_snapshot!(log, @">  INFO Second log");
+
+    let log = TracingCollector::init_info_level().without_prefix();
+    tracing::info!("Third log");
+    let text = log.to_string();
+    assert!(text.starts_with("   INFO  Third log"), "{text:?}");
+}
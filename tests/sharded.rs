@@ -0,0 +1,59 @@
+use std::thread;
+use tracing_collector::TracingCollector;
+
+#[test]
+fn test_sharded_writer_keeps_order() {
+    let log = TracingCollector::builder()
+        .snapshot()
+        .with_sharded_writer(true)
+        .init();
+    tracing::info!("First");
+    for name in ["first", "second"] {
+        thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = log.bind();
+                tracing::info!(thread = name, "From worker");
+            });
+        });
+        tracing::info!("Between");
+    }
+    let cp = log.checkpoint();
+    tracing::info!("Last");
+
+    insta::assert_snapshot!(log.since(cp), @"㏒INFO sharded: Last");
+    insta::assert_snapshot!(log, @r###"
+    ㏒INFO sharded: First
+    INFO sharded: From worker thread="first"
+    INFO sharded: Between
+    INFO sharded: From worker thread="second"
+    INFO sharded: Between
+    INFO sharded: Last
+    "###);
+}
+
+#[test]
+fn test_sharded_writer_concurrent_and_bounded() {
+    let log = TracingCollector::builder()
+        .snapshot()
+        .with_sharded_writer(true)
+        .with_max_events(50)
+        .init();
+    thread::scope(|s| {
+        for t in 0..4 {
+            let log = &log;
+            s.spawn(move || {
+                let _guard = log.bind();
+                for i in 0..1000 {
+                    tracing::info!(t, i, "Log");
+                }
+            });
+        }
+    });
+
+    let text = log.render().prefix(None).to_string();
+    assert_eq!(text.lines().count(), 50);
+    assert!(text
+        .lines()
+        .all(|line| line.starts_with("INFO sharded: Log")));
+    assert!(log.dropped_bytes() > 0);
+}
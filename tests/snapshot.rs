@@ -0,0 +1,22 @@
+use tracing_collector::TracingCollector;
+
+#[tracing::instrument]
+fn handle(user: &str, id: u64) {
+    tracing::info!(status = 200, elapsed = ?Some(3), "Handled");
+}
+
+#[test]
+fn test_snapshot_format() {
+    let log = TracingCollector::builder().snapshot().init();
+    tracing::info_span!("server", port = 8080).in_scope(|| {
+        handle("bob", 1);
+        tracing::warn!(zone = "eu", attempt = 2, "Retrying");
+    });
+    tracing::debug!("Done");
+
+    insta::assert_snapshot!(log, @r###"
+    ㏒INFO snapshot: server{port=8080}: handle{id=1 user="bob"}: Handled elapsed=Some(3) status=200
+    WARN snapshot: server{port=8080}: Retrying attempt=2 zone="eu"
+    DEBUG snapshot: Done
+    "###);
+}
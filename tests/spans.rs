@@ -0,0 +1,53 @@
+use tracing::instrument;
+use tracing_collector::{SpanEventKind, TracingCollector};
+
+#[instrument(fields(size))]
+fn load(path: &str) -> usize {
+    tracing::Span::current().record("size", 12);
+    store();
+    12
+}
+
+#[instrument]
+fn request(id: u64) {
+    load("a.txt");
+}
+
+#[instrument]
+fn store() {}
+
+#[test]
+fn test_span_tree() {
+    let log = TracingCollector::init_debug_level();
+    request(42);
+
+    insta::assert_snapshot!(log.span_tree(), @r###"
+    request{id=42}
+      load{path="a.txt" size=12}
+        store
+    "###);
+}
+
+#[test]
+fn test_span_events() {
+    let log = TracingCollector::init_debug_level();
+    let span = tracing::info_span!("outer", user = tracing::field::Empty);
+    span.in_scope(|| {});
+    span.record("user", "bob");
+    drop(span);
+
+    let events = log
+        .span_events()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    insta::assert_snapshot!(events, @r###"
+    new outer
+    enter outer
+    exit outer
+    record outer{user="bob"}
+    close outer
+    "###);
+    assert_eq!(log.span_events()[0].kind, SpanEventKind::New);
+}
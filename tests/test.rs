@@ -5,7 +5,7 @@ fn test_logs() {
     let log = TracingCollector::init_debug_level();
     tracing::info!("First log");
 
-    insta::assert_display_snapshot!(log, @r###"
+    insta::assert_snapshot!(log, @r###"
     ㏒   INFO  First log
         at tests/test.rs:6
 
@@ -14,7 +14,7 @@ fn test_logs() {
     tracing::debug!("Second log");
     tracing::info!("Third log");
 
-    insta::assert_display_snapshot!(log, @r###"
+    insta::assert_snapshot!(log, @r###"
     ㏒  DEBUG  Second log
         at tests/test.rs:14
 
@@ -0,0 +1,74 @@
+use std::thread;
+use tracing_collector::{FieldValue, TracingCollector};
+
+fn messages(log: &TracingCollector) -> Vec<String> {
+    log.events()
+        .into_iter()
+        .filter_map(|event| event.message)
+        .collect()
+}
+
+#[test]
+fn test_global_spawned_thread_in_span() {
+    let log = TracingCollector::builder().global().init();
+    let span = tracing::info_span!("worker");
+    thread::spawn(move || span.in_scope(|| tracing::info!("From worker")))
+        .join()
+        .unwrap();
+    thread::spawn(|| tracing::info!("Not collected"))
+        .join()
+        .unwrap();
+    tracing::info!("From test");
+
+    assert_eq!(messages(&log), ["From worker", "From test"]);
+}
+
+#[test]
+fn test_global_collectors_are_isolated() {
+    let handles = (0..4i64)
+        .map(|i| {
+            thread::spawn(move || {
+                let log = TracingCollector::builder().global().init();
+                for _ in 0..10 {
+                    tracing::info!(i, "Log");
+                }
+                let events = log.events();
+                assert_eq!(events.len(), 10);
+                assert!(events
+                    .iter()
+                    .all(|e| e.field("i") == Some(&FieldValue::I64(i))));
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn test_bind_spawned_thread() {
+    for log in [
+        TracingCollector::init_info_level(),
+        TracingCollector::builder().global().init(),
+    ] {
+        thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = log.bind();
+                tracing::info!("From worker");
+            });
+        });
+        assert_eq!(messages(&log), ["From worker"]);
+    }
+}
+
+#[test]
+fn test_global_event_in_span_created_before_the_collector() {
+    // install the global subscriber first, as another test could have done
+    drop(TracingCollector::builder().global().init());
+    let _span = tracing::info_span!("fixture").entered();
+    let log = TracingCollector::builder().global().init();
+    tracing::info!("In fixture");
+
+    assert_eq!(messages(&log), ["In fixture"]);
+    assert!(log.peek().contains("In fixture"), "{log}");
+}
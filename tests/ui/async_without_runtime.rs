@@ -0,0 +1,4 @@
+#[tracing_collector::test]
+async fn test_async() {}
+
+fn main() {}
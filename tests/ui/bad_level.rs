@@ -0,0 +1,4 @@
+#[tracing_collector::test(level = "verbose")]
+fn test_level() {}
+
+fn main() {}
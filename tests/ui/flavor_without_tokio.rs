@@ -0,0 +1,4 @@
+#[tracing_collector::test(flavor = "multi_thread")]
+fn test_flavor() {}
+
+fn main() {}
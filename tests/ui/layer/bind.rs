@@ -0,0 +1,6 @@
+use tracing_collector::TracingCollector;
+
+fn main() {
+    let (_layer, log) = TracingCollector::layer::<tracing_subscriber::Registry>();
+    let _guard = log.bind();
+}
@@ -0,0 +1,4 @@
+#[tracing_collector::test(level = "debug", filter = "my_crate=trace")]
+fn test_level_and_filter() {}
+
+fn main() {}
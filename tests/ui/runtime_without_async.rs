@@ -0,0 +1,4 @@
+#[tracing_collector::test(tokio)]
+fn test_sync() {}
+
+fn main() {}
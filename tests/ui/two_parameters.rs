@@ -0,0 +1,8 @@
+#[tracing_collector::test]
+fn test_parameters(
+    log: tracing_collector::TracingCollector,
+    other: tracing_collector::TracingCollector,
+) {
+}
+
+fn main() {}
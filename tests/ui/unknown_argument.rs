@@ -0,0 +1,4 @@
+#[tracing_collector::test(levle = "debug")]
+fn test_typo() {}
+
+fn main() {}
@@ -0,0 +1,60 @@
+use futures_core::Stream;
+use std::{future, pin::Pin, thread, time::Duration};
+use tracing::Level;
+use tracing_collector::{CollectedEvent, EventMatcher, EventStream, TracingCollector};
+
+#[test]
+fn test_wait_for() {
+    let log = TracingCollector::init_info_level();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let _guard = log.bind();
+            thread::sleep(Duration::from_millis(50));
+            tracing::info!("Worker started");
+            tracing::warn!(job = 1, "Job done");
+        });
+
+        let event = log
+            .wait_for(
+                EventMatcher::new().message_contains("done"),
+                Duration::from_secs(5),
+            )
+            .expect("timed out");
+        assert_eq!(event.to_string(), "WARN wait: Job done job=1");
+    });
+
+    assert!(log
+        .wait_for(Level::ERROR, Duration::from_millis(10))
+        .is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_next_event_and_stream() {
+    let log = TracingCollector::init_info_level();
+    let mut stream = log.event_stream();
+
+    let worker = tokio::spawn(log.instrument(async {
+        for step in 0..3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            tracing::info!(step, "Working");
+        }
+    }));
+    let event = log.next_event().await;
+    assert_eq!(event.message.as_deref(), Some("Working"));
+    worker.await.unwrap();
+
+    let mut steps = vec![];
+    for _ in 0..3 {
+        let event = next(&mut stream).await.unwrap();
+        steps.push(event.field("step").unwrap().to_string());
+    }
+    assert_eq!(steps, ["0", "1", "2"]);
+
+    // the stream ends when the collector is dropped
+    drop(log);
+    assert!(next(&mut stream).await.is_none());
+}
+
+async fn next(stream: &mut EventStream) -> Option<CollectedEvent> {
+    future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
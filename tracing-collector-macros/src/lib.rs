@@ -0,0 +1,230 @@
+//! The `#[tracing_collector::test]` attribute macro. See the `tracing-collector` crate for documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, spanned::Spanned, FnArg, Ident, ItemFn, LitInt,
+    LitStr, Pat, PatIdent, ReturnType, Type,
+};
+
+/// The runtime used for `async` tests.
+enum Runtime {
+    Tokio,
+    AsyncStd,
+}
+
+#[derive(Default)]
+struct Args {
+    level: Option<LitStr>,
+    filter: Option<LitStr>,
+    global: bool,
+    snapshot: bool,
+    runtime: Option<Runtime>,
+    flavor: Option<LitStr>,
+    worker_threads: Option<LitInt>,
+}
+
+impl Args {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("level") {
+            self.level = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("filter") {
+            self.filter = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("global") {
+            self.global = true;
+        } else if meta.path.is_ident("snapshot") {
+            self.snapshot = true;
+        } else if meta.path.is_ident("tokio") {
+            self.runtime = Some(Runtime::Tokio);
+        } else if meta.path.is_ident("async_std") {
+            self.runtime = Some(Runtime::AsyncStd);
+        } else if meta.path.is_ident("flavor") {
+            self.flavor = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("worker_threads") {
+            self.worker_threads = Some(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error(
+                "expected one of `level`, `filter`, `global`, `snapshot`, `tokio`, `async_std`, \
+                 `flavor` or `worker_threads`",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The expression creating the `TracingCollector`.
+    fn collector(&self) -> syn::Result<TokenStream2> {
+        let mut builder = quote!(::tracing_collector::TracingCollector::builder());
+        if let Some(level) = &self.level {
+            let level = match level.value().to_lowercase().as_str() {
+                "trace" => quote!(TRACE),
+                "debug" => quote!(DEBUG),
+                "info" => quote!(INFO),
+                "warn" => quote!(WARN),
+                "error" => quote!(ERROR),
+                _ => {
+                    return Err(syn::Error::new(
+                        level.span(),
+                        "expected one of `trace`, `debug`, `info`, `warn` or `error`",
+                    ))
+                }
+            };
+            builder =
+                quote!(#builder.with_max_level(::tracing_collector::__private::Level::#level));
+        }
+        if let Some(filter) = &self.filter {
+            if self.level.is_some() {
+                return Err(syn::Error::new(
+                    filter.span(),
+                    "`level` and `filter` can't be used together, add the level to the filter instead, \
+                     e.g. `filter = \"debug,my_crate=trace\"`",
+                ));
+            }
+            builder = quote!(#builder.with_env_filter(#filter));
+        }
+        if self.global {
+            builder = quote!(#builder.global());
+        }
+        Ok(quote!(#builder.init()))
+    }
+
+    /// The attribute running the test function.
+    fn test_attr(&self) -> syn::Result<TokenStream2> {
+        if self.runtime.is_none() && (self.flavor.is_some() || self.worker_threads.is_some()) {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`flavor` and `worker_threads` require `tokio`",
+            ));
+        }
+        Ok(match self.runtime {
+            None => quote!(#[::core::prelude::v1::test]),
+            Some(Runtime::Tokio) => {
+                let mut args = vec![];
+                if let Some(flavor) = &self.flavor {
+                    args.push(quote!(flavor = #flavor));
+                }
+                if let Some(worker_threads) = &self.worker_threads {
+                    args.push(quote!(worker_threads = #worker_threads));
+                }
+                quote!(#[::tokio::test(#(#args),*)])
+            }
+            Some(Runtime::AsyncStd) => quote!(#[::async_std::test]),
+        })
+    }
+}
+
+/// Turns a function into a test that collects its traces with a `TracingCollector`.
+///
+/// The collector is bound to `log`, or passed as the function's parameter if it has one:
+///
+/// ```rust,ignore
+/// #[tracing_collector::test(level = "debug")]
+/// fn test_logs(log: TracingCollector) {
+///     tracing::info!("First log");
+///     insta::assert_snapshot!(log, @"...");
+/// }
+/// ```
+///
+/// Arguments:
+///
+/// - `level = "debug"`: collect traces up to the level (`trace`, `debug`, `info`, `warn` or `error`).
+/// - `filter = "my_crate=trace,hyper=warn"`: collect traces according to `EnvFilter` directives, instead of
+///   `level`.
+/// - `global`: create a global collector, see `TracingCollectorBuilder::global`.
+/// - `snapshot`: snapshot the traces that were not read by the test with `insta::assert_snapshot!` at the end
+///   of the test. Requires `insta` as a dependency.
+/// - `tokio`, `async_std`: run an `async` test with `#[tokio::test]` or `#[async_std::test]`. The test's future
+///   is wrapped with `TracingCollector::instrument`, so that traces are collected on any worker thread.
+/// - `flavor = "multi_thread"`, `worker_threads = 4`: passed to `#[tokio::test]`.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut parsed = Args::default();
+    let parser = syn::meta::parser(|meta| parsed.parse(meta));
+    parse_macro_input!(args with parser);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand(parsed, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(args: Args, mut item: ItemFn) -> syn::Result<TokenStream2> {
+    let (pat, ty) = collector_binding(&mut item)?;
+    let binding = &pat.ident;
+    let ty = ty.map(|ty| quote!(: #ty));
+    let collector = args.collector()?;
+    let test_attr = args.test_attr()?;
+
+    let is_async = item.sig.asyncness.is_some();
+    if is_async != args.runtime.is_some() {
+        return Err(syn::Error::new(
+            item.sig.fn_token.span(),
+            "async tests require `tokio` or `async_std` and sync tests don't support them",
+        ));
+    }
+
+    let body = &item.block;
+    let ret = match &item.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let run = if is_async {
+        quote! {
+            #binding.instrument(async {
+                let result: #ret = #body;
+                result
+            }).await
+        }
+    } else {
+        quote!((|| -> #ret #body)())
+    };
+    let snapshot = args
+        .snapshot
+        .then(|| quote!(::insta::assert_snapshot!(#binding.to_string());));
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let sig = &item.sig;
+    Ok(quote! {
+        #test_attr
+        #(#attrs)*
+        #vis #sig {
+            let #pat #ty = #collector;
+            let result = #run;
+            #snapshot
+            result
+        }
+    })
+}
+
+/// Remove the collector parameter from the function, if any, and return the pattern (and type) to bind the
+/// collector to.
+fn collector_binding(item: &mut ItemFn) -> syn::Result<(PatIdent, Option<Box<Type>>)> {
+    let inputs = std::mem::take(&mut item.sig.inputs);
+    if let Some(extra) = inputs.iter().nth(1) {
+        return Err(syn::Error::new(
+            extra.span(),
+            "expected at most one parameter for the `TracingCollector`",
+        ));
+    }
+    match inputs.into_iter().next() {
+        None => Ok((
+            PatIdent {
+                attrs: vec![],
+                by_ref: None,
+                mutability: None,
+                ident: Ident::new("log", Span::call_site()),
+                subpat: None,
+            },
+            None,
+        )),
+        Some(FnArg::Typed(arg)) => match *arg.pat {
+            Pat::Ident(pat) => Ok((pat, Some(arg.ty))),
+            pat => Err(syn::Error::new(pat.span(), "expected an identifier")),
+        },
+        Some(arg @ FnArg::Receiver(_)) => Err(syn::Error::new(
+            arg.span(),
+            "expected a `TracingCollector` parameter",
+        )),
+    }
+}